@@ -0,0 +1,195 @@
+use std::fmt;
+
+use super::grid_primitives::*;
+use super::dijkstra::*;
+
+// Numbers describing the texture of a maze, for comparing what different
+// generators make. Straight runs, turns and link directions are judged from
+// rows and columns, so they mean most on flat grids.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MazeStats {
+    pub cell_count: usize,
+    // cells with one way out
+    pub dead_ends: usize,
+    // cells with three or more ways out
+    pub junctions: usize,
+    // cells with two ways out on opposite sides
+    pub straights: usize,
+    // cells with two ways out that aren't opposite
+    pub turns: usize,
+    // steps along passages between junctions and dead ends
+    pub average_corridor_length: f64,
+    pub max_corridor_length: usize,
+    // steps along the longest path Dijkstra finds
+    pub diameter: usize,
+    // steps from the start to the goal, None if it can't be walked
+    pub solution_length: Option<usize>,
+    // links along a row and links along a column
+    pub east_links: usize,
+    pub north_links: usize,
+}
+
+impl MazeStats {
+    pub fn dead_end_ratio(&self) -> f64 {
+        ratio(self.dead_ends, self.cell_count)
+    }
+
+    pub fn east_percentage(&self) -> f64 {
+        100.0 * ratio(self.east_links, self.east_links + self.north_links)
+    }
+
+    pub fn north_percentage(&self) -> f64 {
+        100.0 * ratio(self.north_links, self.east_links + self.north_links)
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 { 0.0 } else { count as f64 / total as f64 }
+}
+
+fn is_straight(pos: GridPos, links: &[GridPos]) -> bool {
+    links.iter().all(|other| other.level == pos.level && other.row == pos.row)
+        || links.iter().all(|other| other.level == pos.level && other.col == pos.col)
+}
+
+// The solution runs from start to goal, usually the maze's entrance and exit
+pub fn analyse<G: MazeGrid + ?Sized>(grid: &G, start: GridPos, goal: GridPos) -> MazeStats {
+    let positions = grid.positions();
+    let links_of = |pos: GridPos| grid.links(pos);
+    let count_where = |f: &dyn Fn(GridPos, &[GridPos]) -> bool| {
+        positions.iter().filter(|&&pos| f(pos, &links_of(pos))).count()
+    };
+
+    // Walk out of every junction and dead end along each passage until
+    // reaching another. Each corridor is walked once from each end, which
+    // leaves the average and max as they are.
+    let is_node = |pos: GridPos| links_of(pos).len() != 2;
+    let corridors: Vec<usize> = positions.iter()
+        .filter(|&&pos| is_node(pos))
+        .flat_map(|&start| links_of(start).into_iter().map(move |first| (start, first)))
+        .map(|(start, first)| {
+            let (mut previous, mut current, mut length) = (start, first, 1);
+            while !is_node(current) && current != start {
+                // the way on is whichever link isn't the one back, which on
+                // a narrow wrapped grid can go to the same cell
+                let mut links = links_of(current);
+                let back = links.iter().position(|&other| other == previous).unwrap();
+                links.swap_remove(back);
+                let next = links[0];
+                previous = current;
+                current = next;
+                length += 1;
+            }
+            length
+        })
+        .collect();
+
+    let links: Vec<(GridPos, GridPos)> = positions.iter()
+        .flat_map(|&pos| links_of(pos).into_iter().map(move |other| (pos, other)))
+        .filter(|(pos, other)| pos < other)
+        .collect();
+
+    let solution_length = match Dijkstra::new(start).run_to_completion(grid).path_to(goal, grid) {
+        path if path.is_empty() => None,
+        path => Some(path.len() - 1),
+    };
+
+    MazeStats {
+        cell_count: positions.len(),
+        dead_ends: count_where(&|_, links| links.len() == 1),
+        junctions: count_where(&|_, links| links.len() >= 3),
+        straights: count_where(&|pos, links| links.len() == 2 && is_straight(pos, links)),
+        turns: count_where(&|pos, links| links.len() == 2 && !is_straight(pos, links)),
+        average_corridor_length: ratio(corridors.iter().sum(), corridors.len()),
+        max_corridor_length: corridors.iter().copied().max().unwrap_or(0),
+        diameter: longest_path(grid).map_or(0, |longest| longest.path.len() - 1),
+        solution_length,
+        east_links: links.iter().filter(|(pos, other)| pos.level == other.level && pos.row == other.row).count(),
+        north_links: links.iter().filter(|(pos, other)| pos.level == other.level && pos.col == other.col).count(),
+    }
+}
+
+impl fmt::Display for MazeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "cells:              {}", self.cell_count)?;
+        writeln!(f, "dead ends:          {} ({:.1}%)", self.dead_ends, 100.0 * self.dead_end_ratio())?;
+        writeln!(f, "junctions:          {}", self.junctions)?;
+        writeln!(f, "straights:          {}", self.straights)?;
+        writeln!(f, "turns:              {}", self.turns)?;
+        writeln!(f, "corridor length:    {:.2} average, {} max",
+                 self.average_corridor_length, self.max_corridor_length)?;
+        writeln!(f, "diameter:           {}", self.diameter)?;
+        match self.solution_length {
+            Some(length) => writeln!(f, "solution length:    {}", length)?,
+            None => writeln!(f, "solution length:    unsolvable")?,
+        }
+        write!(f, "link directions:    {:.1}% east, {:.1}% north", self.east_percentage(), self.north_percentage())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::analyse;
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn counts_the_cells_of_a_small_maze() {
+        // a corridor up the west side and along the south side, with a
+        // spur north from the middle of the south side and three cells
+        // left unlinked
+        let grid = ImmutableGrid::new(3, 3)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::East)
+            .link_cells(pos(0, 0), Direction::North)
+            .link_cells(pos(1, 0), Direction::North)
+            .link_cells(pos(0, 1), Direction::North);
+        let stats = analyse(&grid, pos(0, 0), pos(2, 2));
+        assert_eq!(stats.dead_ends, 3);
+        assert_eq!(stats.junctions, 1);
+        assert_eq!(stats.straights, 1);
+        assert_eq!(stats.turns, 1);
+        assert_eq!(stats.max_corridor_length, 3);
+        assert!((stats.average_corridor_length - 5.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.east_links, 2);
+        assert_eq!(stats.north_links, 3);
+        assert_eq!(stats.diameter, 4);
+        assert_eq!(stats.solution_length, None);
+    }
+
+    #[test]
+    fn walks_corridors_that_loop_round_a_narrow_wrapped_grid() {
+        // the east and west walls of the bottom row both lead to the other
+        // cell, so the corridor out of the junction comes straight back
+        let grid = ImmutableGrid::new(2, 2).with_wrap_mode(WrapMode::Horizontal)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 0), Direction::West)
+            .link_cells(pos(0, 0), Direction::North);
+        let stats = analyse(&grid, pos(0, 0), pos(1, 1));
+        assert_eq!(stats.junctions, 1);
+        assert_eq!(stats.max_corridor_length, 2);
+    }
+
+    #[test]
+    fn binary_tree_is_all_east_and_north() {
+        let grid = ImmutableGrid::new(10, 10).run_binary_tree_algorithm();
+        let stats = analyse(&grid, pos(0, 0), pos(9, 9));
+        assert_eq!(stats.east_links + stats.north_links, 10 * 10 - 1);
+        assert_eq!(stats.dead_ends + stats.junctions + stats.straights + stats.turns, 10 * 10);
+        assert!(stats.diameter >= stats.solution_length.unwrap());
+        assert!((stats.east_percentage() + stats.north_percentage() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn solution_runs_between_the_given_ends() {
+        // a corridor along the bottom row
+        let grid = (0..4).fold(ImmutableGrid::new(5, 1), |grid, col| grid.link_cells(pos(0, col), Direction::East));
+        assert_eq!(analyse(&grid, pos(0, 0), pos(0, 4)).solution_length, Some(4));
+        assert_eq!(analyse(&grid, pos(0, 1), pos(0, 3)).solution_length, Some(2));
+    }
+}
@@ -0,0 +1,232 @@
+use rpds::HashTrieMap;
+use rpds::RedBlackTreeSet;
+
+use super::grid_primitives::*;
+
+// A* search from a start cell to a goal. Like Dijkstra it expands cells in
+// order of distance, but ordered by the distance so far plus a guess at the
+// distance still to go, so it heads for the goal rather than flooding the
+// whole maze. The guess must never be more than the real distance for the
+// path to be the shortest, which holds for these heuristics on flat grids
+// but not across the edges of wrapped ones.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Heuristic {
+    // steps along the rows and columns (and levels) ignoring walls
+    Manhattan,
+    // straight line distance, rounded down
+    Euclidean,
+    // no guess at all, which makes A* expand cells like Dijkstra
+    Zero,
+}
+
+impl Heuristic {
+    pub fn estimate(self, from: GridPos, to: GridPos) -> u32 {
+        let rows = (from.row.0 as i64 - to.row.0 as i64).abs();
+        let cols = (from.col.0 as i64 - to.col.0 as i64).abs();
+        let levels = (from.level.0 as i64 - to.level.0 as i64).abs();
+        match self {
+            Heuristic::Manhattan => (rows + cols + levels) as u32,
+            Heuristic::Euclidean => ((rows * rows + cols * cols + levels * levels) as f64).sqrt() as u32,
+            Heuristic::Zero => 0,
+        }
+    }
+}
+
+pub struct AStar {
+    pub start: GridPos,
+    pub goal: GridPos,
+    pub heuristic: Heuristic,
+    // the shortest known distance from the start to each cell seen so far
+    pub distances: HashTrieMap<GridPos, u32>,
+    // ordered by estimated total distance, then by the estimate still to
+    // go so ties favour cells nearer the goal
+    pub open: RedBlackTreeSet<(u32, u32, GridPos)>,
+    // the cells expanded so far, and the order they were expanded in
+    pub expanded: HashTrieMap<GridPos, usize>,
+    // how many cells had been expanded when each cell joined the open set,
+    // so the open set after any number of steps can be played back
+    pub opened: HashTrieMap<GridPos, usize>,
+}
+
+impl AStar {
+    pub fn new(start: GridPos, goal: GridPos, heuristic: Heuristic) -> Self {
+        let estimate = heuristic.estimate(start, goal);
+        Self {
+            start,
+            goal,
+            heuristic,
+            distances: HashTrieMap::new().insert(start, 0u32),
+            open: RedBlackTreeSet::new().insert((estimate, estimate, start)),
+            expanded: HashTrieMap::new(),
+            opened: HashTrieMap::new().insert(start, 0),
+        }
+    }
+
+    pub fn run_to_completion<G: MazeGrid + ?Sized>(self, grid: &G) -> Self {
+        let mut state = self;
+        while let Some(next) = state.step(grid) {
+            state = next;
+        }
+        state
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.expanded.contains_key(&self.goal) || self.open.is_empty()
+    }
+
+    // One step expands the most promising cell in the open set. None once
+    // the goal has been expanded or there's nowhere left to look.
+    pub fn step<G: MazeGrid + ?Sized>(&self, grid: &G) -> Option<Self> {
+        if self.is_finished() {
+            return None
+        }
+        let &(estimate, to_go, pos) = self.open.first()?;
+        let mut open = self.open.remove(&(estimate, to_go, pos));
+        let mut distances = self.distances.clone();
+        let mut opened = self.opened.clone();
+        let expanded = self.expanded.insert(pos, self.expanded.size());
+        let d = distances[&pos];
+
+        grid.links(pos).into_iter()
+            .filter(|linked_pos| !expanded.contains_key(linked_pos))
+            .for_each(|linked_pos| {
+                let distance = d + 1;
+                let to_go = self.heuristic.estimate(linked_pos, self.goal);
+                match distances.get(&linked_pos) {
+                    Some(&existing) if existing <= distance => (),
+                    existing => {
+                        if let Some(&existing) = existing {
+                            open = open.remove(&(existing + to_go, to_go, linked_pos));
+                        }
+                        distances = distances.insert(linked_pos, distance);
+                        open = open.insert((distance + to_go, to_go, linked_pos));
+                        if !opened.contains_key(&linked_pos) {
+                            opened = opened.insert(linked_pos, expanded.size());
+                        }
+                    }
+                }
+            });
+
+        Some(Self {
+            start: self.start,
+            goal: self.goal,
+            heuristic: self.heuristic,
+            distances,
+            open,
+            expanded,
+            opened,
+        })
+    }
+
+    // The cells in the open set once `steps` cells had been expanded
+    pub fn open_after(&self, steps: usize) -> Vec<GridPos> {
+        self.opened.iter()
+            .filter(|&(pos, &opened)| {
+                opened <= steps && self.expanded.get(pos).map_or(true, |&order| order >= steps)
+            })
+            .map(|(&pos, _)| pos)
+            .collect()
+    }
+
+    // The path from the start to the goal, walking back through expanded
+    // cells one closer to the start each time. Empty until the goal has
+    // been expanded.
+    pub fn path<G: MazeGrid + ?Sized>(&self, grid: &G) -> Vec<GridPos> {
+        if !self.expanded.contains_key(&self.goal) {
+            return Vec::new()
+        }
+
+        let mut distance = self.distances[&self.goal];
+        let mut path = vec![self.goal];
+        let mut current = self.goal;
+        while distance > 0 {
+            current = grid.links(current).into_iter()
+                .find(|pos| self.expanded.contains_key(pos)
+                      && self.distances.get(pos) == Some(&(distance - 1)))
+                .unwrap();
+            distance -= 1;
+            path.push(current);
+        }
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::dijkstra::*;
+    use super::{AStar, Heuristic};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn heuristics_estimate_distance() {
+        assert_eq!(Heuristic::Manhattan.estimate(pos(0, 0), pos(3, 4)), 7);
+        assert_eq!(Heuristic::Euclidean.estimate(pos(0, 0), pos(3, 4)), 5);
+        assert_eq!(Heuristic::Zero.estimate(pos(0, 0), pos(3, 4)), 0);
+    }
+
+    #[test]
+    fn finds_shortest_path_with_every_heuristic() {
+        let grid = ImmutableGrid::new(12, 10).run_recursive_backtracker_algorithm().braid(0.5);
+        let (start, goal) = (pos(0, 0), pos(9, 11));
+        let shortest = Dijkstra::new(start).run_to_completion(&grid).distances[&goal] as usize;
+        [Heuristic::Manhattan, Heuristic::Euclidean, Heuristic::Zero].iter().for_each(|&heuristic| {
+            let search = AStar::new(start, goal, heuristic).run_to_completion(&grid);
+            let path = search.path(&grid);
+            assert_eq!(path.len(), shortest + 1, "{:?}", heuristic);
+            assert_eq!(path[0], start);
+            assert_eq!(path[shortest], goal);
+            path.windows(2).for_each(|step| assert!(grid.links(step[0]).contains(&step[1])));
+        });
+    }
+
+    #[test]
+    fn manhattan_expands_fewer_cells_on_an_open_grid() {
+        // with every wall knocked down the guess is exact and A* walks
+        // straight to the goal
+        let grid = ImmutableGrid::new(10, 10);
+        let grid = grid.positions().into_iter().fold(grid, |grid, p| {
+            [Direction::North, Direction::East].iter().fold(grid, |grid, &dir| grid.link_cells(p, dir))
+        });
+        let manhattan = AStar::new(pos(0, 0), pos(9, 9), Heuristic::Manhattan).run_to_completion(&grid);
+        let zero = AStar::new(pos(0, 0), pos(9, 9), Heuristic::Zero).run_to_completion(&grid);
+        assert_eq!(manhattan.expanded.size(), 19);
+        assert!(zero.expanded.size() > manhattan.expanded.size());
+    }
+
+    #[test]
+    fn plays_back_the_open_set() {
+        let grid = ImmutableGrid::new(6, 5).run_recursive_backtracker_algorithm().braid(0.5);
+        let (start, goal) = (pos(0, 0), pos(4, 5));
+        let mut state = AStar::new(start, goal, Heuristic::Manhattan);
+        let mut states = vec![];
+        while let Some(next) = state.step(&grid) {
+            states.push(state);
+            state = next;
+        }
+        states.push(state);
+        let finished = states.last().unwrap();
+        states.iter().enumerate().for_each(|(steps, state)| {
+            let mut open: Vec<GridPos> = state.open.iter().map(|&(_, _, pos)| pos).collect();
+            let mut played_back = finished.open_after(steps);
+            open.sort();
+            played_back.sort();
+            assert_eq!(open, played_back, "after {} steps", steps);
+        });
+    }
+
+    #[test]
+    fn unreachable_goal_has_no_path() {
+        let grid = ImmutableGrid::new(3, 3);
+        let search = AStar::new(pos(0, 0), pos(2, 2), Heuristic::Manhattan).run_to_completion(&grid);
+        assert!(search.is_finished());
+        assert!(search.path(&grid).is_empty());
+        assert_eq!(search.expanded.size(), 1);
+    }
+}
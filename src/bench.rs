@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::dijkstra::Dijkstra;
+use super::flat_dijkstra::FlatDijkstra;
+
+// Times the persistent Dijkstra against the flat array one, run with
+//
+//   cargo run --release -- bench
+
+const SIZES: [usize; 2] = [70, 2000];
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+pub fn run() {
+    println!("{:>10} {:>14} {:>14} {:>20} {:>9}", "size", "persistent", "flat", "flat + conversion", "speedup");
+    SIZES.iter().for_each(|&size| {
+        let grid = ImmutableGrid::new(size, size).run_binary_tree_algorithm();
+        let root = GridPos::new(Row(size / 2), Col(size / 2));
+
+        let (persistent, persistent_time) = time(|| Dijkstra::new(root).run_to_completion(&grid));
+        let (flat, flat_time) = time(|| FlatDijkstra::run(root, &grid));
+        let (_, conversion_time) = time(|| flat.to_dijkstra());
+        assert_eq!(flat.max_distance, persistent.max_distance);
+
+        println!("{:>10} {:>12.1}ms {:>12.1}ms {:>18.1}ms {:>8.1}x",
+                 format!("{}x{}", size, size),
+                 persistent_time.as_secs_f64() * 1000.0,
+                 flat_time.as_secs_f64() * 1000.0,
+                 (flat_time + conversion_time).as_secs_f64() * 1000.0,
+                 persistent_time.as_secs_f64() / flat_time.as_secs_f64());
+    });
+}
@@ -0,0 +1,132 @@
+use std::time::Instant;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::analysis::*;
+
+// Generates a batch of mazes with each algorithm and prints how they
+// compare, run with
+//
+//   cargo run --release -- compare [--count N] [--size N] [--csv]
+
+const DEFAULT_COUNT: usize = 20;
+const DEFAULT_SIZE: usize = 20;
+
+pub type Generator = fn(ImmutableGrid) -> ImmutableGrid;
+
+pub const ALGORITHMS: [(&str, Generator); 4] = [
+    ("binary tree", ImmutableGrid::run_binary_tree_algorithm),
+    ("sidewinder", ImmutableGrid::run_sidewinder_algorithm),
+    ("recursive backtracker", ImmutableGrid::run_recursive_backtracker_algorithm),
+    ("aldous-broder", ImmutableGrid::run_aldous_broder_algorithm),
+];
+
+#[derive(Debug, PartialEq)]
+struct Settings {
+    count: usize,
+    size: usize,
+    csv: bool,
+}
+
+fn parse_settings(args: &[String]) -> Result<Settings, String> {
+    let mut settings = Settings { count: DEFAULT_COUNT, size: DEFAULT_SIZE, csv: false };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" => settings.csv = true,
+            "--count" | "--size" => {
+                let value = args.next()
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .filter(|&value| value > 0)
+                    .ok_or(format!("{} needs a number above 0", arg))?;
+                if arg == "--count" { settings.count = value } else { settings.size = value }
+            },
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    Ok(settings)
+}
+
+// The mean and standard deviation
+fn summarise(values: &[f64]) -> (f64, f64) {
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count;
+    (mean, variance.sqrt())
+}
+
+pub fn run(args: &[String]) {
+    let settings = match parse_settings(args) {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("usage: compare [--count N] [--size N] [--csv]");
+            std::process::exit(2)
+        }
+    };
+
+    if settings.csv {
+        println!("algorithm,size,count,dead_ends_mean,dead_ends_stddev,diameter_mean,diameter_stddev,\
+                  time_ms_mean,time_ms_stddev");
+    } else {
+        println!("{} mazes of {}x{} per algorithm", settings.count, settings.size, settings.size);
+        println!("{:<22} {:>18} {:>18} {:>18}", "algorithm", "dead ends", "diameter", "time (ms)");
+    }
+
+    // the grids have no openings, so the solution crosses from corner to
+    // corner
+    let corner = GridPos::new(Row(0), Col(0));
+    let opposite_corner = GridPos::new(Row(settings.size - 1), Col(settings.size - 1));
+    ALGORITHMS.iter().for_each(|&(name, generate)| {
+        let mut dead_ends: Vec<f64> = Vec::new();
+        let mut diameters: Vec<f64> = Vec::new();
+        let mut times: Vec<f64> = Vec::new();
+        (0..settings.count).for_each(|_| {
+            let start = Instant::now();
+            let grid = generate(ImmutableGrid::new(settings.size, settings.size));
+            times.push(start.elapsed().as_secs_f64() * 1000.0);
+            let stats = analyse(&grid, corner, opposite_corner);
+            dead_ends.push(stats.dead_ends as f64);
+            diameters.push(stats.diameter as f64);
+        });
+
+        let (dead_ends, diameters, times) = (summarise(&dead_ends), summarise(&diameters), summarise(&times));
+        if settings.csv {
+            println!("{},{},{},{:.2},{:.2},{:.2},{:.2},{:.3},{:.3}", name, settings.size, settings.count,
+                     dead_ends.0, dead_ends.1, diameters.0, diameters.1, times.0, times.1);
+        } else {
+            let cell = |(mean, stddev): (f64, f64)| format!("{:.1} ± {:.1}", mean, stddev);
+            println!("{:<22} {:>18} {:>18} {:>18}", name, cell(dead_ends), cell(diameters), cell(times));
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_settings, summarise, Settings};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_the_settings() {
+        assert_eq!(parse_settings(&args(&[])), Ok(Settings { count: 20, size: 20, csv: false }));
+        assert_eq!(parse_settings(&args(&["--size", "8", "--csv", "--count", "3"])),
+                   Ok(Settings { count: 3, size: 8, csv: true }));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(parse_settings(&args(&["--count"])), Err("--count needs a number above 0".to_string()));
+        assert_eq!(parse_settings(&args(&["--size", "0"])), Err("--size needs a number above 0".to_string()));
+        assert_eq!(parse_settings(&args(&["--size", "big"])), Err("--size needs a number above 0".to_string()));
+        assert_eq!(parse_settings(&args(&["--fast"])), Err("unknown argument --fast".to_string()));
+    }
+
+    #[test]
+    fn summarises_mean_and_standard_deviation() {
+        assert_eq!(summarise(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), (5.0, 2.0));
+        assert_eq!(summarise(&[3.0]), (3.0, 0.0));
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::HashSet;
+use im::hashmap::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+use super::immutable_grid::GridCell;
+
+// A maze on the six faces of a cube. Each face is a size x size grid and
+// GridPos::level is the face. Faces are laid out like the cross-shaped net
+//
+//          [top]
+//   [left][front][right][back]
+//          [bottom]
+//
+// so the faces' rows and columns line up with their neighbours in the net.
+// Passages that cross the edges not joined in the net are found by folding
+// the cube up in 3D.
+
+pub const CUBE_FACES: usize = 6;
+
+type Vec3 = [i64; 3];
+
+struct Face {
+    // outward normal, the corner at row 0 col 0, and the unit steps for
+    // increasing col and row, all in cube coordinates where the cube
+    // spans 0..size on each axis
+    normal: Vec3,
+    origin: Vec3,
+    col_axis: Vec3,
+    row_axis: Vec3,
+    // where the face sits in the net, in faces from the bottom left
+    net_col: usize,
+    net_row: usize,
+}
+
+fn faces(size: i64) -> [Face; CUBE_FACES] {
+    [
+        // front
+        Face { normal: [0, 0, 1], origin: [0, 0, size], col_axis: [1, 0, 0], row_axis: [0, 1, 0],
+               net_col: 1, net_row: 1 },
+        // right
+        Face { normal: [1, 0, 0], origin: [size, 0, size], col_axis: [0, 0, -1], row_axis: [0, 1, 0],
+               net_col: 2, net_row: 1 },
+        // back
+        Face { normal: [0, 0, -1], origin: [size, 0, 0], col_axis: [-1, 0, 0], row_axis: [0, 1, 0],
+               net_col: 3, net_row: 1 },
+        // left
+        Face { normal: [-1, 0, 0], origin: [0, 0, 0], col_axis: [0, 0, 1], row_axis: [0, 1, 0],
+               net_col: 0, net_row: 1 },
+        // top
+        Face { normal: [0, 1, 0], origin: [0, size, size], col_axis: [1, 0, 0], row_axis: [0, 0, -1],
+               net_col: 1, net_row: 2 },
+        // bottom
+        Face { normal: [0, -1, 0], origin: [0, 0, 0], col_axis: [1, 0, 0], row_axis: [0, 0, 1],
+               net_col: 1, net_row: 0 },
+    ]
+}
+
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: Vec3, by: i64) -> Vec3 {
+    [a[0] * by, a[1] * by, a[2] * by]
+}
+
+fn dot(a: Vec3, b: Vec3) -> i64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct CubeGrid {
+    size: usize,
+    cells: HashMap<GridPos, GridCell>,
+}
+
+impl CubeGrid {
+    pub fn new(size: usize) -> Self {
+        let grid_cells: HashMap<GridPos, GridCell> =
+            (0..CUBE_FACES).flat_map(|face| {
+                (0..size).flat_map(move |row| {
+                    (0..size).map(move |col| {
+                        let pos = GridPos::on_level(Level(face), Row(row), Col(col));
+                        (pos, GridCell::new(pos))
+                    })
+                })
+            }).collect();
+
+        CubeGrid {
+            size,
+            cells: grid_cells,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Where a face sits in the unfolded net, as (col, row) in faces counted
+    // from the bottom left
+    pub fn net_position(&self, face: Level) -> (usize, usize) {
+        let face = &faces(self.size as i64)[face.0];
+        (face.net_col, face.net_row)
+    }
+
+    pub fn get_relative_cell_pos(&self, pos: GridPos, dir: Direction) -> Option<GridPos> {
+        let size = self.size as i64;
+        let (row, col) = (pos.row.0 as i64, pos.col.0 as i64);
+        let (row, col) = match dir {
+            Direction::North => (row + 1, col),
+            Direction::East  => (row, col + 1),
+            Direction::South => (row - 1, col),
+            Direction::West  => (row, col - 1),
+        };
+        if row >= 0 && col >= 0 && row < size && col < size {
+            return Some(GridPos { row: Row(row as usize), col: Col(col as usize), ..pos })
+        }
+
+        // Off the edge of the face. Working in doubled coordinates so cell
+        // centres are whole numbers, step half a cell to the edge and half
+        // a cell down the face we fold onto, whose normal is the way we
+        // were heading.
+        let faces = faces(size);
+        let face = faces.get(pos.level.0)?;
+        let step = match dir {
+            Direction::North => face.row_axis,
+            Direction::East  => face.col_axis,
+            Direction::South => scale(face.row_axis, -1),
+            Direction::West  => scale(face.col_axis, -1),
+        };
+        let centre = add(add(scale(face.origin, 2),
+                             scale(face.col_axis, 2 * pos.col.0 as i64 + 1)),
+                         scale(face.row_axis, 2 * pos.row.0 as i64 + 1));
+        let folded = sub(add(centre, step), face.normal);
+
+        let (other_face, other) = faces.iter().enumerate().find(|(_, f)| f.normal == step)?;
+        let offset = sub(folded, scale(other.origin, 2));
+        let other_col = (dot(offset, other.col_axis) - 1) / 2;
+        let other_row = (dot(offset, other.row_axis) - 1) / 2;
+        Some(GridPos::on_level(Level(other_face), Row(other_row as usize), Col(other_col as usize)))
+    }
+
+    // The direction from `other` that leads back to `pos`. Crossing an edge
+    // of the cube can turn you round, so this isn't always the reverse.
+    fn direction_back(&self, other: GridPos, pos: GridPos) -> Option<Direction> {
+        Direction::iter().find(|&dir| self.get_relative_cell_pos(other, dir) == Some(pos))
+    }
+
+    fn update_cell(self, pos: GridPos, cell: GridCell) -> Self {
+        CubeGrid {
+            size: self.size,
+            cells: self.cells.update(pos, cell),
+        }
+    }
+
+    pub fn get(&self, pos: &GridPos) -> Option<&GridCell> {
+        self.cells.get(pos)
+    }
+
+    fn link_single(self, pos: GridPos, dir: Direction) -> Self {
+        let cell = *self.get(&pos).unwrap();
+        match dir {
+            Direction::North =>
+                self.update_cell(pos, GridCell { north_open: true, ..cell } ),
+            Direction::East =>
+                self.update_cell(pos, GridCell { east_open: true,  ..cell } ),
+            Direction::South =>
+                self.update_cell(pos, GridCell { south_open: true, ..cell } ),
+            Direction::West =>
+                self.update_cell(pos, GridCell { west_open: true,  ..cell } ),
+        }
+    }
+
+    pub fn link_cells(self, pos: GridPos, dir: Direction) -> Self {
+        let other_pos = match self.get_relative_cell_pos(pos, dir) {
+            None => return self,
+            Some(other_pos) => other_pos,
+        };
+        let back = self.direction_back(other_pos, pos).unwrap();
+        self.link_single(pos, dir).link_single(other_pos, back)
+    }
+
+    pub fn positions(&self) -> Vec<GridPos> {
+        let mut positions: Vec<GridPos> = self.iter().map(|cell| cell.pos).collect();
+        positions.sort();
+        positions
+    }
+
+    pub fn run_recursive_backtracker_algorithm(self) -> Self {
+        let mut rng = rand::thread_rng();
+        let start = match self.positions().into_iter().choose(&mut rng) {
+            None => return self,
+            Some(pos) => pos,
+        };
+
+        let mut grid = self;
+        let mut visited: HashSet<GridPos> = HashSet::new();
+        visited.insert(start);
+        let mut stack: Vec<GridPos> = vec![start];
+        while let Some(&pos) = stack.last() {
+            let next = Direction::iter()
+                .filter_map(|dir| grid.get_relative_cell_pos(pos, dir).map(|other| (dir, other)))
+                .filter(|(_, other)| !visited.contains(other))
+                .choose(&mut rng);
+            match next {
+                None => { stack.pop(); },
+                Some((dir, other)) => {
+                    grid = grid.link_cells(pos, dir);
+                    visited.insert(other);
+                    stack.push(other);
+                }
+            }
+        }
+        grid
+    }
+}
+
+impl MazeGrid for CubeGrid {
+    fn positions(&self) -> Vec<GridPos> {
+        CubeGrid::positions(self)
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        let cell = self.get(&pos).unwrap();
+        Direction::iter()
+            .filter(|&dir| cell.is_open_to(dir))
+            .filter_map(|dir| self.get_relative_cell_pos(pos, dir))
+            .collect()
+    }
+}
+
+// **************************
+// Iter
+// **************************
+
+pub struct Iter {
+    cell_iter: Box<dyn Iterator<Item = (GridPos, GridCell)>>
+}
+
+impl CubeGrid {
+    pub fn iter(&self) -> Iter {
+        Iter {
+            cell_iter: Box::new(self.cells.clone().into_iter())
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = GridCell;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cell_iter.next().map(|(_, cell)| cell)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::dijkstra::*;
+    use super::{CubeGrid, CUBE_FACES};
+
+    const FRONT: usize = 0;
+    const RIGHT: usize = 1;
+    const BACK: usize = 2;
+    const LEFT: usize = 3;
+    const TOP: usize = 4;
+    const BOTTOM: usize = 5;
+
+    fn pos(face: usize, row: usize, col: usize) -> GridPos {
+        GridPos::on_level(Level(face), Row(row), Col(col))
+    }
+
+    #[test]
+    fn edges_joined_in_the_net_line_up() {
+        let grid = CubeGrid::new(3);
+        assert_eq!(grid.get_relative_cell_pos(pos(FRONT, 2, 1), Direction::North), Some(pos(TOP, 0, 1)));
+        assert_eq!(grid.get_relative_cell_pos(pos(FRONT, 0, 1), Direction::South), Some(pos(BOTTOM, 2, 1)));
+        assert_eq!(grid.get_relative_cell_pos(pos(FRONT, 1, 2), Direction::East), Some(pos(RIGHT, 1, 0)));
+        assert_eq!(grid.get_relative_cell_pos(pos(BACK, 1, 2), Direction::East), Some(pos(LEFT, 1, 0)));
+    }
+
+    #[test]
+    fn folded_edges_turn_the_corner() {
+        let grid = CubeGrid::new(3);
+        // the top's right edge is the top of the right face, running back
+        // along its row
+        assert_eq!(grid.get_relative_cell_pos(pos(TOP, 0, 2), Direction::East), Some(pos(RIGHT, 2, 0)));
+        assert_eq!(grid.get_relative_cell_pos(pos(TOP, 2, 2), Direction::East), Some(pos(RIGHT, 2, 2)));
+        // the top's far edge is the top of the back face, upside down
+        assert_eq!(grid.get_relative_cell_pos(pos(TOP, 2, 0), Direction::North), Some(pos(BACK, 2, 2)));
+        assert_eq!(grid.get_relative_cell_pos(pos(BACK, 2, 2), Direction::North), Some(pos(TOP, 2, 0)));
+    }
+
+    #[test]
+    fn every_step_off_an_edge_can_be_retraced() {
+        let grid = CubeGrid::new(4);
+        grid.positions().into_iter().for_each(|from| {
+            Direction::iter().for_each(|dir| {
+                let to = grid.get_relative_cell_pos(from, dir).unwrap();
+                let back = Direction::iter()
+                    .filter(|&back| grid.get_relative_cell_pos(to, back) == Some(from))
+                    .count();
+                assert_eq!(back, 1, "{:?} {:?} -> {:?}", from, dir, to);
+            });
+        });
+    }
+
+    #[test]
+    fn recursive_backtracker_covers_every_face() {
+        let grid = CubeGrid::new(5).run_recursive_backtracker_algorithm();
+        let d = Dijkstra::new(pos(FRONT, 0, 0)).run_to_completion(&grid);
+        assert_eq!(d.distances.size(), CUBE_FACES * 5 * 5);
+        let link_count: usize = grid.positions().iter()
+            .map(|&pos| grid.links(pos).len())
+            .sum();
+        assert_eq!(link_count / 2, CUBE_FACES * 5 * 5 - 1);
+    }
+}
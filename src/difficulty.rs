@@ -0,0 +1,150 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use super::grid_primitives::*;
+use super::dijkstra::*;
+
+// How hard a maze is to solve from one cell to another. Harder mazes have a
+// solution that's long for their size, lots of places along it where the
+// solver has to choose, and side branches deep enough that going the wrong
+// way costs a lot before the dead end shows.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difficulty {
+    pub solution_length: usize,
+    pub diameter: usize,
+    // cells on the solution with a way out that isn't along it
+    pub decision_points: usize,
+    // how far the deepest cell hanging off each decision point is from it
+    pub branch_lengths: Vec<usize>,
+    // 0 to 100, made up of the solution length against the diameter, the
+    // share of the solution that's decision points and how deep the
+    // branches go against the diameter
+    pub score: f64,
+}
+
+const LENGTH_WEIGHT: f64 = 40.0;
+const DECISION_WEIGHT: f64 = 30.0;
+const BRANCH_WEIGHT: f64 = 30.0;
+
+impl Difficulty {
+    pub fn average_branch_length(&self) -> f64 {
+        if self.branch_lengths.is_empty() {
+            0.0
+        } else {
+            self.branch_lengths.iter().sum::<usize>() as f64 / self.branch_lengths.len() as f64
+        }
+    }
+}
+
+// None if the goal can't be reached from the start
+pub fn difficulty<G: MazeGrid + ?Sized>(grid: &G, start: GridPos, goal: GridPos) -> Option<Difficulty> {
+    let path = Dijkstra::new(start).run_to_completion(grid).path_to(goal, grid);
+    if path.is_empty() {
+        return None
+    }
+    let solution_length = path.len() - 1;
+    let diameter = longest_path(grid).map_or(0, |longest| longest.path.len() - 1);
+
+    // Flooding out from the whole solution at once gives each cell off it
+    // its distance from the solution and the cell it branches off from.
+    let from_path = Dijkstra::from_roots(&path).run_to_completion(grid);
+    let branch_lengths: Vec<usize> = path.iter()
+        .filter(|&&pos| grid.links(pos).iter().any(|other| from_path.distances[other] > 0))
+        .map(|&pos| {
+            from_path.owners.iter()
+                .filter(|&(_, &owner)| owner == pos)
+                .map(|(other, _)| from_path.distances[other] as usize)
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut difficulty = Difficulty {
+        solution_length,
+        diameter,
+        decision_points: branch_lengths.len(),
+        branch_lengths,
+        score: 0.0,
+    };
+    let ratio = |a: f64, b: f64| if b == 0.0 { 0.0 } else { (a / b).min(1.0) };
+    difficulty.score = LENGTH_WEIGHT * ratio(solution_length as f64, diameter as f64)
+        + DECISION_WEIGHT * ratio(difficulty.decision_points as f64, path.len() as f64)
+        + BRANCH_WEIGHT * ratio(difficulty.average_branch_length(), diameter as f64);
+    Some(difficulty)
+}
+
+// Generates mazes until one scores within the band, solving from the first
+// cell to the last. None if none of the attempts do.
+pub fn generate_within<G, F>(band: RangeInclusive<f64>, attempts: usize, mut generate: F)
+        -> Option<(G, Difficulty)>
+        where G: MazeGrid, F: FnMut() -> G {
+    (0..attempts).find_map(|_| {
+        let grid = generate();
+        let positions = grid.positions();
+        let (&start, &goal) = (positions.first()?, positions.last()?);
+        let difficulty = difficulty(&grid, start, goal)?;
+        if band.contains(&difficulty.score) { Some((grid, difficulty)) } else { None }
+    })
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "difficulty {:.1}: solution {} of diameter {}, {} decision points, branches {:.1} deep on average",
+               self.score, self.solution_length, self.diameter, self.decision_points, self.average_branch_length())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::{difficulty, generate_within};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn corridor_has_no_decisions() {
+        let grid = ImmutableGrid::new(5, 1)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::East)
+            .link_cells(pos(0, 2), Direction::East)
+            .link_cells(pos(0, 3), Direction::East);
+        let d = difficulty(&grid, pos(0, 0), pos(0, 4)).unwrap();
+        assert_eq!(d.solution_length, 4);
+        assert_eq!(d.diameter, 4);
+        assert_eq!(d.decision_points, 0);
+        assert!((d.score - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn measures_branches_off_the_solution() {
+        // the solution runs along the bottom row, with a spur two cells long
+        // going north from the middle
+        let grid = ImmutableGrid::new(3, 3)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::East)
+            .link_cells(pos(0, 1), Direction::North)
+            .link_cells(pos(1, 1), Direction::North);
+        let d = difficulty(&grid, pos(0, 0), pos(0, 2)).unwrap();
+        assert_eq!(d.solution_length, 2);
+        assert_eq!(d.decision_points, 1);
+        assert_eq!(d.branch_lengths, vec![2]);
+        assert_eq!(d.diameter, 3);
+    }
+
+    #[test]
+    fn unreachable_goal_has_no_difficulty() {
+        assert_eq!(difficulty(&ImmutableGrid::new(3, 3), pos(0, 0), pos(2, 2)), None);
+    }
+
+    #[test]
+    fn generated_mazes_land_in_the_band() {
+        let (_, d) = generate_within(0.0..=100.0, 1, || ImmutableGrid::new(8, 8).run_recursive_backtracker_algorithm())
+            .unwrap();
+        assert!(d.score >= 0.0 && d.score <= 100.0);
+        assert!(generate_within(101.0..=200.0, 5, || ImmutableGrid::new(8, 8).run_sidewinder_algorithm()).is_none());
+    }
+}
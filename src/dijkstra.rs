@@ -1,27 +1,95 @@
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+use std::rc::Rc;
 use rpds::Queue;
 use rpds::HashTrieMap;
 
 use super::grid_primitives::*;
-use super::immutable_grid::*;
+
+// The two cells furthest apart in a maze and the path between them
+#[derive(Debug, Clone, PartialEq)]
+pub struct LongestPath {
+    pub start: GridPos,
+    pub goal: GridPos,
+    pub path: Vec<GridPos>,
+}
+
+// The distances a finished flood found, whether it kept every step as a
+// Dijkstra or ran flat, so code that only wants the result can take either
+pub trait Distances {
+    fn root(&self) -> GridPos;
+
+    // None if the flood didn't reach the cell
+    fn distance(&self, pos: GridPos) -> Option<u32>;
+
+    fn max_distance(&self) -> u32;
+
+    // The shortest path from the root to the target, found by walking back
+    // from the target through cells one closer to the root each time. Empty
+    // if the target hasn't been reached.
+    fn path_to<G: MazeGrid + ?Sized>(&self, target: GridPos, grid: &G) -> Vec<GridPos> {
+        let mut distance = match self.distance(target) {
+            None => return Vec::new(),
+            Some(distance) => distance,
+        };
+
+        let mut path = vec![target];
+        let mut current = target;
+        while distance > 0 {
+            current = grid.links(current).into_iter()
+                .find(|&pos| self.distance(pos) == Some(distance - 1))
+                .unwrap();
+            distance -= 1;
+            path.push(current);
+        }
+        path.reverse();
+        path
+    }
+}
 
 pub struct Dijkstra {
     pub root: GridPos,
+    // every cell the flood started from, the first of which is `root`,
+    // shared by every step of the flood
+    pub roots: Rc<[GridPos]>,
     pub distances: HashTrieMap<GridPos, u32>,
+    // the root each reached cell is nearest to
+    pub owners: HashTrieMap<GridPos, GridPos>,
     pub frontier: Queue<GridPos>,
     pub max_distance: u32,
 }
 
 impl Dijkstra {
     pub fn new(root: GridPos) -> Self {
+        Self::from_roots(&[root])
+    }
+
+    // Floods out from all the roots at once, so distances are to the
+    // nearest root and each cell is owned by the root it's nearest to.
+    // Cells equally near two roots go to whichever reaches them first.
+    // There has to be at least one root.
+    pub fn from_roots(roots: &[GridPos]) -> Self {
+        assert!(!roots.is_empty(), "a flood needs somewhere to start");
         Self {
-            root: root,
-            distances: HashTrieMap::new().insert(root, 0u32),
-            frontier: Queue::new().enqueue(root),
+            root: roots[0],
+            roots: Rc::from(roots),
+            distances: roots.iter().fold(HashTrieMap::new(), |distances, &root| distances.insert(root, 0u32)),
+            owners: roots.iter().fold(HashTrieMap::new(), |owners, &root| owners.insert(root, root)),
+            frontier: roots.iter().fold(Queue::new(), |frontier, &root| frontier.enqueue(root)),
             max_distance: 0u32,
-        } 
+        }
+    }
+
+    pub fn owner_of(&self, pos: GridPos) -> Option<GridPos> {
+        self.owners.get(&pos).copied()
     }
 
-    pub fn run_to_completion(self, grid: &ImmutableGrid) -> Self {
+    // How many of the reached cells are nearest to the root
+    pub fn territory_size(&self, root: GridPos) -> usize {
+        self.owners.values().filter(|&&owner| owner == root).count()
+    }
+
+    pub fn run_to_completion<G: MazeGrid + ?Sized>(self, grid: &G) -> Self {
         let mut state = self;
         while let Some(next) = state.step(grid) {
             state = next;
@@ -29,46 +97,257 @@ impl Dijkstra {
         state
     }
 
-    pub fn run_to_completion_all(self, grid: &ImmutableGrid) -> Vec<Self> {
-        let mut states = Vec::new();
-        states.push(self);
-        while let Some(next) = states.last().unwrap().step(grid) {
-            states.push(next);
-        }
-        states
+    // The reached cell furthest from the root, and its distance
+    pub fn furthest(&self) -> (GridPos, u32) {
+        self.distances.iter()
+            .max_by_key(|&(&pos, &distance)| (distance, pos))
+            .map(|(&pos, &distance)| (pos, distance))
+            .unwrap()
     }
 
     // One step will explore the frontier of the next cell in the frontier
-    pub fn step(&self, grid: &ImmutableGrid) -> Option<Self> {
+    pub fn step<G: MazeGrid + ?Sized>(&self, grid: &G) -> Option<Self> {
         let pos = self.frontier.peek();
         pos.map(|pos| {
             let mut frontier = self.frontier.dequeue().unwrap();
-            let cell = grid.get(&pos).unwrap();
-            let d = self.distances.get(&pos).unwrap();
+            let d = self.distances.get(pos).unwrap();
+            let owner = self.owners[pos];
             let mut distances = self.distances.clone();
+            let mut owners = self.owners.clone();
             let mut max_distance = self.max_distance;
 
-            Direction::iter().for_each(|dir| {
-                if cell.is_open_to(dir) {
-                    let linked_pos = grid.get_relative_cell_pos(cell.pos, dir);
-                    if let Some(linked_pos) = linked_pos {
-                        if !distances.contains_key(&linked_pos) {
-                            distances = distances.insert(linked_pos, d + 1);
-                            frontier = frontier.enqueue(linked_pos);
-                            if d + 1 > max_distance { max_distance += 1  }
-                        }
-                    }
+            grid.links(*pos).into_iter().for_each(|linked_pos| {
+                if !distances.contains_key(&linked_pos) {
+                    distances = distances.insert(linked_pos, d + 1);
+                    owners = owners.insert(linked_pos, owner);
+                    frontier = frontier.enqueue(linked_pos);
+                    if d + 1 > max_distance { max_distance += 1  }
                 }
             });
 
             Self {
                 root: self.root,
-                distances: distances,
-                frontier: frontier,
-                max_distance: max_distance,
+                roots: Rc::clone(&self.roots),
+                distances,
+                owners,
+                frontier,
+                max_distance,
             } 
         })
     }
+
+    // The distance of the next cell to be explored, None once finished
+    fn frontier_distance(&self) -> Option<u32> {
+        self.frontier.peek().map(|pos| self.distances[pos])
+    }
+}
+
+impl Distances for Dijkstra {
+    fn root(&self) -> GridPos {
+        self.root
+    }
+
+    fn distance(&self, pos: GridPos) -> Option<u32> {
+        self.distances.get(&pos).copied()
+    }
+
+    fn max_distance(&self) -> u32 {
+        self.max_distance
+    }
+}
+
+// A cell being reached during a flood: its distance, and how many steps
+// had been taken when it was reached, so roots have step 0
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LogEntry {
+    pub pos: GridPos,
+    pub distance: u32,
+    pub step: u32,
+}
+
+// Everything a flood did, kept as one entry per cell rather than a Dijkstra
+// per step, which can rebuild the flood as it was after any step. Cells are
+// explored in the order they're reached, so step n explores entry n - 1 and
+// whatever it reached belongs to the same root as that entry does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DijkstraLog {
+    pub roots: Vec<GridPos>,
+    pub entries: Vec<LogEntry>,
+}
+
+impl DijkstraLog {
+    // Floods the grid from the roots, recording as it goes
+    pub fn record<G: MazeGrid + ?Sized>(roots: &[GridPos], grid: &G) -> Self {
+        assert!(!roots.is_empty(), "a flood needs somewhere to start");
+        let mut entries: Vec<LogEntry> = Vec::new();
+        let mut reached: HashMap<GridPos, u32> = HashMap::new();
+        let mut frontier: VecDeque<GridPos> = VecDeque::new();
+        roots.iter().for_each(|&root| {
+            reached.insert(root, 0);
+            frontier.push_back(root);
+            entries.push(LogEntry { pos: root, distance: 0, step: 0 });
+        });
+
+        let mut step = 0;
+        while let Some(pos) = frontier.pop_front() {
+            step += 1;
+            let d = reached[&pos];
+            grid.links(pos).into_iter().for_each(|linked_pos| {
+                if let Entry::Vacant(entry) = reached.entry(linked_pos) {
+                    entry.insert(d + 1);
+                    frontier.push_back(linked_pos);
+                    entries.push(LogEntry { pos: linked_pos, distance: d + 1, step });
+                }
+            });
+        }
+
+        DijkstraLog { roots: roots.to_vec(), entries }
+    }
+
+    // How many steps the flood took to finish, one per reached cell
+    pub fn step_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    // The flood as it was after the given number of steps, the same as
+    // stepping a fresh Dijkstra that many times. This builds it from
+    // nothing, next_state and previous_state move one step at a time.
+    pub fn state_at(&self, step: usize) -> Dijkstra {
+        let step = step.min(self.step_count());
+        let reached = self.entries.partition_point(|entry| entry.step as usize <= step);
+        let entries = &self.entries[..reached];
+
+        // the owner of each entry, by index into the roots
+        let owner_indexes = entries.iter().enumerate().fold(Vec::new(), |mut owners, (i, entry)| {
+            let owner = match entry.step {
+                0 => i,
+                step => owners[step as usize - 1],
+            };
+            owners.push(owner);
+            owners
+        });
+
+        Dijkstra {
+            root: self.roots[0],
+            roots: Rc::from(self.roots.as_slice()),
+            distances: entries.iter()
+                .fold(HashTrieMap::new(), |distances, entry| distances.insert(entry.pos, entry.distance)),
+            owners: entries.iter().zip(owner_indexes)
+                .fold(HashTrieMap::new(), |owners, (entry, owner)| owners.insert(entry.pos, self.roots[owner])),
+            frontier: entries[step..].iter().fold(Queue::new(), |frontier, entry| frontier.enqueue(entry.pos)),
+            max_distance: entries.iter().map(|entry| entry.distance).max().unwrap_or(0),
+        }
+    }
+
+    pub fn final_state(&self) -> Dijkstra {
+        self.state_at(self.step_count())
+    }
+
+    // The entries reached by the given step
+    fn reached_by(&self, step: usize) -> &[LogEntry] {
+        let from = self.entries.partition_point(|entry| (entry.step as usize) < step);
+        let to = self.entries.partition_point(|entry| entry.step as usize <= step);
+        &self.entries[from..to]
+    }
+
+    // The flood one step on from `state`, which must be the flood after
+    // `step` steps. Only the cells that step reached are added, so playing
+    // the log forwards doesn't rebuild the flood each time. None once the
+    // flood is finished.
+    pub fn next_state(&self, state: &Dijkstra, step: usize) -> Option<Dijkstra> {
+        let explored = self.entries.get(step)?;
+        let owner = state.owners[&explored.pos];
+        let reached = self.reached_by(step + 1);
+        Some(Dijkstra {
+            root: state.root,
+            roots: Rc::clone(&state.roots),
+            distances: reached.iter()
+                .fold(state.distances.clone(), |distances, entry| distances.insert(entry.pos, entry.distance)),
+            owners: reached.iter()
+                .fold(state.owners.clone(), |owners, entry| owners.insert(entry.pos, owner)),
+            frontier: reached.iter()
+                .fold(state.frontier.dequeue().unwrap(), |frontier, entry| frontier.enqueue(entry.pos)),
+            max_distance: reached.iter().map(|entry| entry.distance).fold(state.max_distance, u32::max),
+        })
+    }
+
+    // The flood one step back from `state`, which must be the flood after
+    // `step` steps. The cells that step reached are taken out again, and
+    // as a queue can't be added to at the front the frontier is rebuilt
+    // from the log. None before the first step.
+    pub fn previous_state(&self, state: &Dijkstra, step: usize) -> Option<Dijkstra> {
+        let step = step.checked_sub(1)?;
+        let reached = self.entries.partition_point(|entry| entry.step as usize <= step);
+        Some(Dijkstra {
+            root: state.root,
+            roots: Rc::clone(&state.roots),
+            distances: self.reached_by(step + 1).iter()
+                .fold(state.distances.clone(), |distances, entry| distances.remove(&entry.pos)),
+            owners: self.reached_by(step + 1).iter()
+                .fold(state.owners.clone(), |owners, entry| owners.remove(&entry.pos)),
+            frontier: self.entries[step..reached].iter()
+                .fold(Queue::new(), |frontier, entry| frontier.enqueue(entry.pos)),
+            max_distance: self.entries[..reached].last().map_or(0, |entry| entry.distance),
+        })
+    }
+}
+
+// Finds a shortest path by flooding from both ends at once and stopping
+// soon after the floods meet, rather than flooding the whole maze from the
+// start. Empty if the goal can't be reached.
+pub fn bidirectional_path<G: MazeGrid + ?Sized>(start: GridPos, goal: GridPos, grid: &G) -> Vec<GridPos> {
+    let mut forward = Dijkstra::new(start);
+    let mut backward = Dijkstra::new(goal);
+    // the shortest known path length and the cell where its halves meet
+    let mut best: Option<(u32, GridPos)> = if start == goal { Some((0, start)) } else { None };
+
+    while let (Some(f), Some(b)) = (forward.frontier_distance(), backward.frontier_distance()) {
+        // Any path shorter than the best must pass through a cell both
+        // floods have reached, so once the frontiers are far enough out
+        // the best can't be beaten.
+        if best.map_or(false, |(length, _)| f + b >= length) {
+            break
+        }
+
+        let (searching, other) = if f <= b { (&mut forward, &backward) } else { (&mut backward, &forward) };
+        let pos = *searching.frontier.peek().unwrap();
+        *searching = searching.step(grid).unwrap();
+        grid.links(pos).into_iter().for_each(|linked_pos| {
+            if let (Some(&d1), Some(&d2)) = (searching.distances.get(&linked_pos), other.distances.get(&linked_pos)) {
+                if best.map_or(true, |(length, _)| d1 + d2 < length) {
+                    best = Some((d1 + d2, linked_pos));
+                }
+            }
+        });
+    }
+
+    match best {
+        None => Vec::new(),
+        Some((_, meeting)) => {
+            let mut path = forward.path_to(meeting, grid);
+            let mut rest = backward.path_to(meeting, grid);
+            rest.pop();
+            rest.reverse();
+            path.extend(rest);
+            path
+        }
+    }
+}
+
+// Finds the longest path in a perfect maze (its diameter) with two passes of
+// Dijkstra: the cell furthest from any starting cell is one end of a longest
+// path, and the cell furthest from that is the other. Mazes with loops get a
+// long path this way but not necessarily the longest. None for an empty grid.
+pub fn longest_path<G: MazeGrid + ?Sized>(grid: &G) -> Option<LongestPath> {
+    let first = *grid.positions().first()?;
+    let (start, _) = Dijkstra::new(first).run_to_completion(grid).furthest();
+    let from_start = Dijkstra::new(start).run_to_completion(grid);
+    let (goal, _) = from_start.furthest();
+    Some(LongestPath {
+        start,
+        goal,
+        path: from_start.path_to(goal, grid),
+    })
 }
 
 
@@ -76,11 +355,11 @@ impl Dijkstra {
 mod test {
     use super::super::grid_primitives::*;
     use super::super::immutable_grid::*;
-    use super::Dijkstra;
+    use super::{Dijkstra, Distances, DijkstraLog, longest_path, bidirectional_path};
 
     #[test]
     fn should_do_16_steps_in_4x4_grid() {
-        let grid = ImmutableGrid::new(4, 4);
+        let grid = ImmutableGrid::new(4, 4).run_sidewinder_algorithm();
         let mut d = Dijkstra::new(GridPos::new(Row(0), Col(0)));
         let mut steps = 0;
         while let Some(next) = d.step(&grid) {
@@ -92,7 +371,7 @@ mod test {
 
     #[test]
     fn should_visit_each_pos_in_2x2_grid() {
-        let grid = ImmutableGrid::new(2, 2);
+        let grid = ImmutableGrid::new(2, 2).run_sidewinder_algorithm();
         let d = Dijkstra::new(GridPos::new(Row(0), Col(0)));
         let d = d.run_to_completion(&grid);
         let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
@@ -102,7 +381,7 @@ mod test {
 
     #[test]
     fn should_visit_each_pos_in_4x4_grid() {
-        let grid = ImmutableGrid::new(2, 2);
+        let grid = ImmutableGrid::new(2, 2).run_sidewinder_algorithm();
         let d = Dijkstra::new(GridPos::new(Row(0), Col(0)));
         let d = d.run_to_completion(&grid);
         let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
@@ -112,7 +391,7 @@ mod test {
 
     #[test]
     fn should_visit_each_pos_in_4x8_grid() {
-        let grid = ImmutableGrid::new(4, 8);
+        let grid = ImmutableGrid::new(4, 8).run_sidewinder_algorithm();
         let d = Dijkstra::new(GridPos::new(Row(0), Col(0)));
         let d = d.run_to_completion(&grid);
         let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
@@ -122,7 +401,7 @@ mod test {
 
     #[test]
     fn should_visit_each_pos_in_8x30_grid() {
-        let grid = ImmutableGrid::new(8, 30);
+        let grid = ImmutableGrid::new(8, 30).run_sidewinder_algorithm();
         let d = Dijkstra::new(GridPos::new(Row(0), Col(0)));
         let d = d.run_to_completion(&grid);
         let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
@@ -131,8 +410,72 @@ mod test {
     }
 
     #[test]
-    fn should_have_all_distances_at_least_up_to_6() {
+    fn should_visit_each_pos_in_wrapped_grid() {
+        let grid = ImmutableGrid::new(9, 7)
+            .with_wrap_mode(WrapMode::Both)
+            .run_recursive_backtracker_algorithm();
+        let d = Dijkstra::new(GridPos::new(Row(0), Col(0)));
+        let d = d.run_to_completion(&grid);
+        let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
+        distance_positions.sort();
+        assert_eq!(distance_positions, grid.positions());
+    }
+
+    #[test]
+    fn path_to_should_walk_linked_cells_from_root_to_target() {
+        let grid = ImmutableGrid::new(8, 6).run_sidewinder_algorithm();
+        let root = GridPos::new(Row(0), Col(0));
+        let target = GridPos::new(Row(5), Col(7));
+        let d = Dijkstra::new(root).run_to_completion(&grid);
+        let path = d.path_to(target, &grid);
+        assert_eq!(path.first(), Some(&root));
+        assert_eq!(path.last(), Some(&target));
+        assert_eq!(path.len() as u32, d.distances.get(&target).unwrap() + 1);
+        path.windows(2).for_each(|step| {
+            assert!(grid.links(step[0]).contains(&step[1]));
+        });
+    }
+
+    #[test]
+    fn path_to_unreached_target_should_be_empty() {
         let grid = ImmutableGrid::new(4, 4);
+        let d = Dijkstra::new(GridPos::new(Row(0), Col(0))).run_to_completion(&grid);
+        assert!(d.path_to(GridPos::new(Row(3), Col(3)), &grid).is_empty());
+    }
+
+    #[test]
+    fn longest_path_in_a_corridor_runs_end_to_end() {
+        let grid = (0..4).fold(ImmutableGrid::new(5, 1), |grid, col| {
+            grid.link_cells(GridPos::new(Row(0), Col(col)), Direction::East)
+        });
+        let longest = longest_path(&grid).unwrap();
+        let mut ends = vec![longest.start, longest.goal];
+        ends.sort();
+        assert_eq!(ends, vec![GridPos::new(Row(0), Col(0)), GridPos::new(Row(0), Col(4))]);
+        assert_eq!(longest.path.len(), 5);
+    }
+
+    #[test]
+    fn longest_path_is_at_least_as_long_as_any_path_from_its_start() {
+        let grid = ImmutableGrid::new(12, 9).run_recursive_backtracker_algorithm();
+        let longest = longest_path(&grid).unwrap();
+        assert_eq!(longest.path.first(), Some(&longest.start));
+        assert_eq!(longest.path.last(), Some(&longest.goal));
+        // in a perfect maze no two cells are further apart than the ends
+        grid.positions().into_iter().for_each(|pos| {
+            let (_, distance) = Dijkstra::new(pos).run_to_completion(&grid).furthest();
+            assert!((distance as usize) < longest.path.len());
+        });
+    }
+
+    #[test]
+    fn longest_path_of_empty_grid_is_none() {
+        assert_eq!(longest_path(&ImmutableGrid::new(0, 0)), None);
+    }
+
+    #[test]
+    fn should_have_all_distances_at_least_up_to_6() {
+        let grid = ImmutableGrid::new(4, 4).run_sidewinder_algorithm();
         let d = Dijkstra::new(GridPos::new(Row(0), Col(0)));
         let d = d.run_to_completion(&grid);
         for x in 0u32..7u32 {
@@ -140,4 +483,94 @@ mod test {
         }
     }
 
-}
\ No newline at end of file
+
+    #[test]
+    fn multiple_roots_share_out_the_cells() {
+        let grid = ImmutableGrid::new(9, 7).run_recursive_backtracker_algorithm().braid(0.5);
+        let roots = [GridPos::new(Row(0), Col(0)), GridPos::new(Row(6), Col(8)), GridPos::new(Row(3), Col(4))];
+        let d = Dijkstra::from_roots(&roots).run_to_completion(&grid);
+        assert_eq!(roots.iter().map(|&root| d.territory_size(root)).sum::<usize>(), 9 * 7);
+
+        let singles: Vec<Dijkstra> = roots.iter().map(|&root| Dijkstra::new(root).run_to_completion(&grid)).collect();
+        grid.positions().into_iter().for_each(|pos| {
+            let nearest = singles.iter().map(|single| single.distances[&pos]).min().unwrap();
+            assert_eq!(d.distances[&pos], nearest);
+            let owner = d.owner_of(pos).unwrap();
+            let owner_distance = singles[roots.iter().position(|&root| root == owner).unwrap()].distances[&pos];
+            assert_eq!(owner_distance, nearest);
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn multiple_roots_need_at_least_one() {
+        Dijkstra::from_roots(&[]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn log_needs_at_least_one_root() {
+        DijkstraLog::record(&[], &ImmutableGrid::new(3, 3));
+    }
+
+    #[test]
+    fn bidirectional_path_is_a_shortest_path() {
+        let grid = ImmutableGrid::new(12, 10).run_recursive_backtracker_algorithm().braid(0.5);
+        let (start, goal) = (GridPos::new(Row(0), Col(0)), GridPos::new(Row(9), Col(11)));
+        let path = bidirectional_path(start, goal, &grid);
+        let shortest = Dijkstra::new(start).run_to_completion(&grid).path_to(goal, &grid);
+        assert_eq!(path.len(), shortest.len());
+        assert_eq!(path[0], start);
+        assert_eq!(path[path.len() - 1], goal);
+        path.windows(2).for_each(|step| assert!(grid.links(step[0]).contains(&step[1])));
+
+        assert_eq!(bidirectional_path(start, start, &grid), vec![start]);
+        assert!(bidirectional_path(start, goal, &ImmutableGrid::new(12, 10)).is_empty());
+    }
+
+    #[test]
+    fn log_rebuilds_every_step() {
+        let grid = ImmutableGrid::new(8, 6).run_recursive_backtracker_algorithm().braid(0.5);
+        let roots = [GridPos::new(Row(0), Col(0)), GridPos::new(Row(5), Col(7))];
+        let log = DijkstraLog::record(&roots, &grid);
+        assert_eq!(log.step_count(), 8 * 6);
+
+        let mut d = Dijkstra::from_roots(&roots);
+        (0..=log.step_count()).for_each(|step| {
+            let rebuilt = log.state_at(step);
+            assert_eq!(rebuilt.distances, d.distances, "step {}", step);
+            assert_eq!(rebuilt.owners, d.owners, "step {}", step);
+            assert!(rebuilt.frontier.iter().eq(d.frontier.iter()), "step {}", step);
+            assert_eq!(rebuilt.max_distance, d.max_distance, "step {}", step);
+            if let Some(next) = d.step(&grid) {
+                d = next;
+            }
+        });
+    }
+
+    #[test]
+    fn log_plays_forwards_and_backwards() {
+        let grid = ImmutableGrid::new(8, 6).run_recursive_backtracker_algorithm().braid(0.5);
+        let roots = [GridPos::new(Row(0), Col(0)), GridPos::new(Row(5), Col(7))];
+        let log = DijkstraLog::record(&roots, &grid);
+        let same = |played: &Dijkstra, step: usize| {
+            let rebuilt = log.state_at(step);
+            assert_eq!(played.distances, rebuilt.distances, "step {}", step);
+            assert_eq!(played.owners, rebuilt.owners, "step {}", step);
+            assert!(played.frontier.iter().eq(rebuilt.frontier.iter()), "step {}", step);
+            assert_eq!(played.max_distance, rebuilt.max_distance, "step {}", step);
+        };
+
+        let mut d = log.state_at(0);
+        assert!(log.previous_state(&d, 0).is_none());
+        (0..log.step_count()).for_each(|step| {
+            d = log.next_state(&d, step).unwrap();
+            same(&d, step + 1);
+        });
+        assert!(log.next_state(&d, log.step_count()).is_none());
+        (1..=log.step_count()).rev().for_each(|step| {
+            d = log.previous_state(&d, step).unwrap();
+            same(&d, step - 1);
+        });
+    }
+}
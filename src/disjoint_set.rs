@@ -0,0 +1,32 @@
+// Tracks which of a set of items have been joined together, numbered from 0.
+// The dungeon joins up its regions with it, and the uniformity test uses
+// it to tell whether a set of passages makes a tree.
+pub struct DisjointSet {
+    parents: Vec<usize>,
+}
+
+impl DisjointSet {
+    pub fn new(size: usize) -> Self {
+        DisjointSet { parents: (0..size).collect() }
+    }
+
+    pub fn find(&mut self, item: usize) -> usize {
+        let parent = self.parents[item];
+        if parent == item {
+            return item
+        }
+        let root = self.find(parent);
+        self.parents[item] = root;
+        root
+    }
+
+    // Joins the two sets, returning false if they were already joined
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false
+        }
+        self.parents[a] = b;
+        true
+    }
+}
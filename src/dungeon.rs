@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::fmt;
+use rand::prelude::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::disjoint_set::DisjointSet;
+
+// A dungeon is a maze of corridors with rectangular rooms in it. Every cell
+// in a room is linked to the cells next to it in the same room, and rooms
+// open onto the corridors through doors. Rooms are kept apart so there's
+// always corridor between them.
+
+// A passage out of a room, from the room cell `pos` towards `dir`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Door {
+    pub pos: GridPos,
+    pub dir: Direction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Room {
+    // the south west corner
+    pub origin: GridPos,
+    pub width: usize,
+    pub height: usize,
+    pub doors: Vec<Door>,
+}
+
+impl Room {
+    pub fn new(origin: GridPos, width: usize, height: usize) -> Self {
+        Room {
+            origin,
+            width,
+            height,
+            doors: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, pos: GridPos) -> bool {
+        pos.level == self.origin.level
+            && pos.row.0 >= self.origin.row.0 && pos.row.0 < self.origin.row.0 + self.height
+            && pos.col.0 >= self.origin.col.0 && pos.col.0 < self.origin.col.0 + self.width
+    }
+
+    pub fn positions(&self) -> Vec<GridPos> {
+        (0..self.height).flat_map(|row| {
+            (0..self.width).map(move |col| {
+                GridPos { row: Row(self.origin.row.0 + row), col: Col(self.origin.col.0 + col), ..self.origin }
+            })
+        }).collect()
+    }
+
+    // Whether the rooms overlap or come within `gap` cells of each other
+    pub fn is_near(&self, other: &Room, gap: usize) -> bool {
+        self.origin.col.0 < other.origin.col.0 + other.width + gap
+            && other.origin.col.0 < self.origin.col.0 + self.width + gap
+            && self.origin.row.0 < other.origin.row.0 + other.height + gap
+            && other.origin.row.0 < self.origin.row.0 + self.height + gap
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct DungeonSettings {
+    // how many times to try to place a room, attempts that would overlap
+    // another room are dropped
+    pub room_attempts: usize,
+    pub min_room_size: usize,
+    pub max_room_size: usize,
+    // every room gets at least one door, after that each wall cell facing
+    // a corridor gets a door with this probability
+    pub extra_door_probability: f64,
+}
+
+impl Default for DungeonSettings {
+    fn default() -> Self {
+        DungeonSettings {
+            room_attempts: 30,
+            min_room_size: 3,
+            max_room_size: 8,
+            extra_door_probability: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DungeonError {
+    // rooms need at least one cell each way, and the smallest can't be
+    // bigger than the largest
+    RoomSizes { min: usize, max: usize },
+}
+
+impl fmt::Display for DungeonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DungeonError::RoomSizes { min, max } =>
+                write!(f, "rooms can't be between {} and {} cells across", min, max),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dungeon {
+    pub grid: ImmutableGrid,
+    pub rooms: Vec<Room>,
+}
+
+impl Dungeon {
+    pub fn new(column_count: usize, row_count: usize, settings: DungeonSettings) -> Result<Self, DungeonError> {
+        if settings.min_room_size == 0 || settings.min_room_size > settings.max_room_size {
+            return Err(DungeonError::RoomSizes { min: settings.min_room_size, max: settings.max_room_size })
+        }
+        let mut rng = rand::thread_rng();
+        let mut rooms = place_rooms(column_count, row_count, &settings, &mut rng);
+
+        let grid = rooms.iter().fold(ImmutableGrid::new(column_count, row_count), |grid, room| {
+            link_room(grid, room)
+        });
+        let room_of: HashMap<GridPos, usize> = rooms.iter().enumerate()
+            .flat_map(|(i, room)| room.positions().into_iter().map(move |pos| (pos, i)))
+            .collect();
+
+        let (grid, corridor_of) = carve_corridors(grid, &room_of, &mut rng);
+
+        // Each room and each separate stretch of corridor starts out as its
+        // own region. Doors go in to join regions together until the whole
+        // dungeon is connected, plus a few extra for loops.
+        let room_count = rooms.len();
+        let region_of = |pos: &GridPos| -> usize {
+            match room_of.get(pos) {
+                Some(&room) => room,
+                None => room_count + corridor_of[pos],
+            }
+        };
+        let region_count = room_count + corridor_of.values().copied().max().map_or(0, |max| max + 1);
+        let mut regions = DisjointSet::new(region_count);
+
+        let mut candidates: Vec<Door> = rooms.iter()
+            .flat_map(|room| {
+                let grid = &grid;
+                room.positions().into_iter().flat_map(move |pos| {
+                    grid.neighbours(pos).into_iter()
+                        .filter(move |&(_, other)| !room.contains(other))
+                        .map(move |(dir, _)| Door { pos, dir })
+                })
+            })
+            .collect();
+        candidates.shuffle(&mut rng);
+
+        let grid = candidates.into_iter().fold(grid, |grid, door| {
+            let other = grid.get_relative_cell_pos(door.pos, door.dir).unwrap();
+            let (from, to) = (region_of(&door.pos), region_of(&other));
+            let joins_regions = regions.union(from, to);
+            if !joins_regions && !rng.gen_bool(settings.extra_door_probability) {
+                return grid
+            }
+
+            rooms[room_of[&door.pos]].doors.push(door);
+            grid.link_cells(door.pos, door.dir)
+        });
+
+        Ok(Dungeon { grid, rooms })
+    }
+
+    pub fn room_at(&self, pos: GridPos) -> Option<&Room> {
+        self.rooms.iter().find(|room| room.contains(pos))
+    }
+}
+
+fn place_rooms<R: Rng>(column_count: usize, row_count: usize,
+                       settings: &DungeonSettings, rng: &mut R) -> Vec<Room> {
+    let mut rooms: Vec<Room> = Vec::new();
+    for _ in 0..settings.room_attempts {
+        let width = rng.gen_range(settings.min_room_size, settings.max_room_size + 1);
+        let height = rng.gen_range(settings.min_room_size, settings.max_room_size + 1);
+        if width > column_count || height > row_count {
+            continue
+        }
+        let origin = GridPos::new(Row(rng.gen_range(0, row_count - height + 1)),
+                                  Col(rng.gen_range(0, column_count - width + 1)));
+        let room = Room::new(origin, width, height);
+        // leave a gap between rooms so corridors can run between them
+        if rooms.iter().all(|other| !room.is_near(other, 1)) {
+            rooms.push(room);
+        }
+    }
+    rooms
+}
+
+fn link_room(grid: ImmutableGrid, room: &Room) -> ImmutableGrid {
+    room.positions().into_iter().fold(grid, |grid, pos| {
+        [Direction::North, Direction::East].iter().fold(grid, |grid, &dir| {
+            match grid.get_relative_cell_pos(pos, dir) {
+                Some(other) if room.contains(other) => grid.link_cells(pos, dir),
+                _ => grid,
+            }
+        })
+    })
+}
+
+// Runs a recursive backtracker through every cell outside the rooms. Cells
+// the backtracker can't reach from one start get a run of their own, and
+// the result records which run (corridor) each cell ended up in.
+fn carve_corridors<R: Rng>(grid: ImmutableGrid, room_of: &HashMap<GridPos, usize>,
+                           rng: &mut R) -> (ImmutableGrid, HashMap<GridPos, usize>) {
+    let mut corridor_of: HashMap<GridPos, usize> = HashMap::new();
+    let mut grid = grid;
+    let mut corridor_count = 0;
+
+    for start in grid.positions() {
+        if room_of.contains_key(&start) || corridor_of.contains_key(&start) {
+            continue
+        }
+
+        corridor_of.insert(start, corridor_count);
+        let mut stack: Vec<GridPos> = vec![start];
+        while let Some(&pos) = stack.last() {
+            let next = grid.neighbours(pos).into_iter()
+                .filter(|(_, other)| !room_of.contains_key(other) && !corridor_of.contains_key(other))
+                .choose(rng);
+            match next {
+                None => { stack.pop(); },
+                Some((dir, other)) => {
+                    grid = grid.link_cells(pos, dir);
+                    corridor_of.insert(other, corridor_count);
+                    stack.push(other);
+                }
+            }
+        }
+        corridor_count += 1;
+    }
+
+    (grid, corridor_of)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::dijkstra::*;
+    use super::{Dungeon, DungeonSettings, DungeonError, Room};
+
+    #[test]
+    fn rooms_do_not_touch() {
+        let dungeon = Dungeon::new(40, 30, DungeonSettings::default()).unwrap();
+        assert!(!dungeon.rooms.is_empty());
+        dungeon.rooms.iter().enumerate().for_each(|(i, room)| {
+            dungeon.rooms[i + 1..].iter().for_each(|other| assert!(!room.is_near(other, 1)));
+        });
+    }
+
+    #[test]
+    fn rooms_are_open_inside_and_have_doors() {
+        let dungeon = Dungeon::new(40, 30, DungeonSettings::default()).unwrap();
+        dungeon.rooms.iter().for_each(|room| {
+            assert!(!room.doors.is_empty());
+            room.positions().into_iter().for_each(|pos| {
+                let cell = dungeon.grid.get(&pos).unwrap();
+                Direction::iter().for_each(|dir| {
+                    if let Some(other) = dungeon.grid.get_relative_cell_pos(pos, dir) {
+                        if room.contains(other) {
+                            assert!(cell.is_open_to(dir));
+                        }
+                    }
+                });
+            });
+            room.doors.iter().for_each(|door| {
+                assert!(room.contains(door.pos));
+                assert!(dungeon.grid.get(&door.pos).unwrap().is_open_to(door.dir));
+            });
+        });
+    }
+
+    #[test]
+    fn every_cell_can_be_reached() {
+        let dungeon = Dungeon::new(40, 30, DungeonSettings::default()).unwrap();
+        let d = Dijkstra::new(GridPos::new(Row(0), Col(0))).run_to_completion(&dungeon.grid);
+        assert_eq!(d.distances.size(), 40 * 30);
+    }
+
+    #[test]
+    fn room_sizes_have_to_make_sense() {
+        let settings = DungeonSettings { min_room_size: 5, max_room_size: 4, ..DungeonSettings::default() };
+        assert_eq!(Dungeon::new(40, 30, settings).unwrap_err(), DungeonError::RoomSizes { min: 5, max: 4 });
+        let settings = DungeonSettings { min_room_size: 0, ..DungeonSettings::default() };
+        assert_eq!(Dungeon::new(40, 30, settings).unwrap_err(), DungeonError::RoomSizes { min: 0, max: 8 });
+    }
+
+    #[test]
+    fn room_covers_its_bounds() {
+        let room = Room::new(GridPos::new(Row(2), Col(3)), 4, 2);
+        assert_eq!(room.positions().len(), 8);
+        assert!(room.contains(GridPos::new(Row(3), Col(6))));
+        assert!(!room.contains(GridPos::new(Row(4), Col(6))));
+        assert!(!room.contains(GridPos::new(Row(2), Col(7))));
+    }
+}
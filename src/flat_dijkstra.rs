@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+use rpds::HashTrieMap;
+use rpds::Queue;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::dijkstra::{Dijkstra, Distances};
+
+// The same flood as Dijkstra but with nothing kept from one step to the
+// next, so it can use a plain array of distances indexed by row and column
+// and a mutable queue. Much faster on big grids when only the finished
+// distances are wanted, which both give through Distances.
+
+const UNREACHED: u32 = u32::MAX;
+
+pub struct FlatDijkstra {
+    pub root: GridPos,
+    column_count: usize,
+    distances: Vec<u32>,
+    pub max_distance: u32,
+}
+
+impl FlatDijkstra {
+    pub fn run(root: GridPos, grid: &ImmutableGrid) -> Self {
+        let column_count = grid.column_count();
+        let index = |pos: GridPos| pos.row.0 * column_count + pos.col.0;
+        let mut distances = vec![UNREACHED; column_count * grid.row_count()];
+        let mut frontier: VecDeque<GridPos> = VecDeque::new();
+        let mut max_distance = 0;
+
+        distances[index(root)] = 0;
+        frontier.push_back(root);
+        while let Some(pos) = frontier.pop_front() {
+            let d = distances[index(pos)];
+            grid.links(pos).into_iter().for_each(|linked_pos| {
+                let linked = index(linked_pos);
+                if distances[linked] == UNREACHED {
+                    distances[linked] = d + 1;
+                    max_distance = max_distance.max(d + 1);
+                    frontier.push_back(linked_pos);
+                }
+            });
+        }
+
+        FlatDijkstra { root, column_count, distances, max_distance }
+    }
+
+    // The finished flood as a Dijkstra, for code that wants the owners or
+    // to carry on stepping. This costs more than the flood itself.
+    pub fn to_dijkstra(&self) -> Dijkstra {
+        let distances = self.distances.iter().enumerate()
+            .filter(|&(_, &distance)| distance != UNREACHED)
+            .fold(HashTrieMap::new(), |distances, (i, &distance)| {
+                let pos = GridPos::new(Row(i / self.column_count), Col(i % self.column_count));
+                distances.insert(pos, distance)
+            });
+        let owners = distances.keys().fold(HashTrieMap::new(), |owners, &pos| owners.insert(pos, self.root));
+
+        Dijkstra {
+            root: self.root,
+            roots: Rc::new([self.root]),
+            distances,
+            owners,
+            frontier: Queue::new(),
+            max_distance: self.max_distance,
+        }
+    }
+}
+
+impl Distances for FlatDijkstra {
+    fn root(&self) -> GridPos {
+        self.root
+    }
+
+    fn distance(&self, pos: GridPos) -> Option<u32> {
+        // past the last column the index would land in the next row
+        if pos.col.0 >= self.column_count {
+            return None
+        }
+        match self.distances.get(pos.row.0 * self.column_count + pos.col.0) {
+            Some(&UNREACHED) | None => None,
+            Some(&distance) => Some(distance),
+        }
+    }
+
+    fn max_distance(&self) -> u32 {
+        self.max_distance
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::dijkstra::*;
+    use super::FlatDijkstra;
+
+    #[test]
+    fn matches_dijkstra() {
+        let grid = ImmutableGrid::new(12, 9).with_wrap_mode(WrapMode::Both)
+            .run_recursive_backtracker_algorithm().braid(0.5);
+        let root = GridPos::new(Row(4), Col(6));
+        let flat = FlatDijkstra::run(root, &grid);
+        let d = Dijkstra::new(root).run_to_completion(&grid);
+        grid.positions().into_iter().for_each(|pos| {
+            assert_eq!(flat.distance(pos), d.distances.get(&pos).copied());
+        });
+        assert_eq!(flat.max_distance, d.max_distance);
+
+        let (goal, _) = d.furthest();
+        assert_eq!(flat.path_to(goal, &grid), d.path_to(goal, &grid));
+
+        let converted = flat.to_dijkstra();
+        assert_eq!(converted.distances, d.distances);
+        assert_eq!(converted.owners, d.owners);
+    }
+
+    #[test]
+    fn unreached_cells_have_no_distance() {
+        let grid = ImmutableGrid::new(3, 3).link_cells(GridPos::new(Row(0), Col(0)), Direction::East);
+        let flat = FlatDijkstra::run(GridPos::new(Row(0), Col(0)), &grid);
+        assert_eq!(flat.distance(GridPos::new(Row(0), Col(1))), Some(1));
+        assert_eq!(flat.distance(GridPos::new(Row(2), Col(2))), None);
+        assert_eq!(flat.to_dijkstra().distances.size(), 2);
+    }
+
+    #[test]
+    fn cells_outside_the_grid_have_no_distance() {
+        let grid = ImmutableGrid::new(3, 2).link_cells(GridPos::new(Row(0), Col(0)), Direction::North);
+        let flat = FlatDijkstra::run(GridPos::new(Row(0), Col(0)), &grid);
+        assert_eq!(flat.distance(GridPos::new(Row(1), Col(0))), Some(1));
+        assert_eq!(flat.distance(GridPos::new(Row(0), Col(3))), None);
+        assert_eq!(flat.distance(GridPos::new(Row(2), Col(0))), None);
+    }
+}
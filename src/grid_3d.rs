@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+use im::hashmap::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+
+// A stack of flat grids, where cells can also link to the cell directly above
+// or below them on the next level. GridPos::level says which level a cell is on.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction3d {
+    North,
+    East,
+    South,
+    West,
+    Up,
+    Down,
+}
+
+impl Direction3d {
+    pub fn reverse_dir(&self) -> Direction3d {
+        match self {
+            Direction3d::North => Direction3d::South,
+            Direction3d::East  => Direction3d::West,
+            Direction3d::South => Direction3d::North,
+            Direction3d::West  => Direction3d::East,
+            Direction3d::Up    => Direction3d::Down,
+            Direction3d::Down  => Direction3d::Up,
+        }
+    }
+
+    pub fn iter() -> impl Iterator<Item = Direction3d> {
+        [Direction3d::North, Direction3d::South, Direction3d::East,
+         Direction3d::West, Direction3d::Up, Direction3d::Down].iter().copied()
+    }
+}
+
+impl From<Direction> for Direction3d {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::North => Direction3d::North,
+            Direction::East  => Direction3d::East,
+            Direction::South => Direction3d::South,
+            Direction::West  => Direction3d::West,
+        }
+    }
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+pub struct GridCell3d {
+    pub pos: GridPos,
+    pub north_open: bool,
+    pub east_open: bool,
+    pub south_open: bool,
+    pub west_open: bool,
+    pub up_open: bool,
+    pub down_open: bool,
+}
+
+impl GridCell3d {
+    pub fn new(pos: GridPos) -> Self {
+        GridCell3d {
+            pos,
+            north_open: false,
+            east_open: false,
+            south_open: false,
+            west_open: false,
+            up_open: false,
+            down_open: false,
+        }
+    }
+
+    pub fn is_open_to(&self, dir: Direction3d) -> bool {
+        match dir {
+            Direction3d::North => self.north_open,
+            Direction3d::East  => self.east_open,
+            Direction3d::South => self.south_open,
+            Direction3d::West  => self.west_open,
+            Direction3d::Up    => self.up_open,
+            Direction3d::Down  => self.down_open,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct Grid3d {
+    level_count: usize,
+    column_count: usize,
+    row_count: usize,
+    cells: HashMap<GridPos, GridCell3d>,
+}
+
+impl Grid3d {
+    pub fn new(column_count: usize, row_count: usize, level_count: usize) -> Self {
+        let grid_cells: HashMap<GridPos, GridCell3d> =
+            (0..level_count).flat_map(|level| {
+                (0..column_count).flat_map(move |col| {
+                    (0..row_count).map(move |row| {
+                        let pos = GridPos::on_level(Level(level), Row(row), Col(col));
+                        (pos, GridCell3d::new(pos))
+                    })
+                })
+            }).collect();
+
+        Grid3d {
+            level_count,
+            column_count,
+            row_count,
+            cells: grid_cells,
+        }
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.level_count
+    }
+
+    pub fn get_relative_cell_pos(&self, pos: GridPos, dir: Direction3d) -> Option<GridPos> {
+        let GridPos { level, row, col } = pos;
+        match dir {
+            Direction3d::North if row.0 + 1 < self.row_count =>
+                Some(GridPos { row: Row(row.0 + 1), ..pos }),
+            Direction3d::East if col.0 + 1 < self.column_count =>
+                Some(GridPos { col: Col(col.0 + 1), ..pos }),
+            Direction3d::South if row.0 > 0 =>
+                Some(GridPos { row: Row(row.0 - 1), ..pos }),
+            Direction3d::West if col.0 > 0 =>
+                Some(GridPos { col: Col(col.0 - 1), ..pos }),
+            Direction3d::Up if level.0 + 1 < self.level_count =>
+                Some(GridPos { level: Level(level.0 + 1), ..pos }),
+            Direction3d::Down if level.0 > 0 =>
+                Some(GridPos { level: Level(level.0 - 1), ..pos }),
+            _ => None,
+        }
+    }
+
+    fn update_cell(self, pos: GridPos, cell: GridCell3d) -> Self {
+        Grid3d {
+            cells: self.cells.update(pos, cell),
+            ..self
+        }
+    }
+
+    pub fn get(&self, pos: &GridPos) -> Option<&GridCell3d> {
+        self.cells.get(pos)
+    }
+
+    fn link_single(self, pos: GridPos, dir: Direction3d) -> Self {
+        let cell = *self.get(&pos).unwrap();
+        match dir {
+            Direction3d::North =>
+                self.update_cell(pos, GridCell3d { north_open: true, ..cell }),
+            Direction3d::East =>
+                self.update_cell(pos, GridCell3d { east_open: true,  ..cell }),
+            Direction3d::South =>
+                self.update_cell(pos, GridCell3d { south_open: true, ..cell }),
+            Direction3d::West =>
+                self.update_cell(pos, GridCell3d { west_open: true,  ..cell }),
+            Direction3d::Up =>
+                self.update_cell(pos, GridCell3d { up_open: true,    ..cell }),
+            Direction3d::Down =>
+                self.update_cell(pos, GridCell3d { down_open: true,  ..cell }),
+        }
+    }
+
+    pub fn link_cells(self, pos: GridPos, dir: Direction3d) -> Self {
+        match self.get_relative_cell_pos(pos, dir) {
+            None => self,
+            Some(other_pos) =>
+                self.link_single(pos, dir)
+                    .link_single(other_pos, dir.reverse_dir()),
+        }
+    }
+
+    pub fn positions(&self) -> Vec<GridPos> {
+        let mut positions: Vec<GridPos> = self.iter().map(|cell| cell.pos).collect();
+        positions.sort();
+        positions
+    }
+
+    pub fn run_recursive_backtracker_algorithm(self) -> Self {
+        let mut rng = rand::thread_rng();
+        let start = match self.positions().into_iter().choose(&mut rng) {
+            None => return self,
+            Some(pos) => pos,
+        };
+
+        let mut grid = self;
+        let mut visited: HashSet<GridPos> = HashSet::new();
+        visited.insert(start);
+        let mut stack: Vec<GridPos> = vec![start];
+        while let Some(&pos) = stack.last() {
+            let next = Direction3d::iter()
+                .filter_map(|dir| grid.get_relative_cell_pos(pos, dir).map(|other| (dir, other)))
+                .filter(|(_, other)| !visited.contains(other))
+                .choose(&mut rng);
+            match next {
+                None => { stack.pop(); },
+                Some((dir, other)) => {
+                    grid = grid.link_cells(pos, dir);
+                    visited.insert(other);
+                    stack.push(other);
+                }
+            }
+        }
+        grid
+    }
+}
+
+impl MazeGrid for Grid3d {
+    fn positions(&self) -> Vec<GridPos> {
+        Grid3d::positions(self)
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        let cell = self.get(&pos).unwrap();
+        Direction3d::iter()
+            .filter(|&dir| cell.is_open_to(dir))
+            .filter_map(|dir| self.get_relative_cell_pos(pos, dir))
+            .collect()
+    }
+}
+
+// **************************
+// Iter
+// **************************
+
+pub struct Iter {
+    cell_iter: Box<dyn Iterator<Item = (GridPos, GridCell3d)>>
+}
+
+impl Grid3d {
+    pub fn iter(&self) -> Iter {
+        Iter {
+            cell_iter: Box::new(self.cells.clone().into_iter())
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = GridCell3d;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cell_iter.next().map(|(_, cell)| cell)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::dijkstra::*;
+    use super::{Direction3d, Grid3d};
+
+    fn pos(level: usize, row: usize, col: usize) -> GridPos {
+        GridPos::on_level(Level(level), Row(row), Col(col))
+    }
+
+    #[test]
+    fn up_and_down_stay_within_the_levels() {
+        let grid = Grid3d::new(2, 2, 3);
+        assert_eq!(grid.get_relative_cell_pos(pos(0, 1, 1), Direction3d::Up), Some(pos(1, 1, 1)));
+        assert_eq!(grid.get_relative_cell_pos(pos(2, 1, 1), Direction3d::Up), None);
+        assert_eq!(grid.get_relative_cell_pos(pos(0, 1, 1), Direction3d::Down), None);
+    }
+
+    #[test]
+    fn linking_up_opens_the_cell_above_downwards() {
+        let grid = Grid3d::new(2, 2, 2).link_cells(pos(0, 0, 1), Direction3d::Up);
+        assert!(grid.get(&pos(1, 0, 1)).unwrap().down_open);
+        assert_eq!(grid.links(pos(1, 0, 1)), vec![pos(0, 0, 1)]);
+    }
+
+    #[test]
+    fn dijkstra_measures_distances_across_levels() {
+        let grid = Grid3d::new(2, 1, 3)
+            .link_cells(pos(0, 0, 0), Direction3d::East)
+            .link_cells(pos(0, 0, 1), Direction3d::Up)
+            .link_cells(pos(1, 0, 1), Direction3d::Up)
+            .link_cells(pos(2, 0, 1), Direction3d::West);
+        let d = Dijkstra::new(pos(0, 0, 0)).run_to_completion(&grid);
+        assert_eq!(d.distances.get(&pos(2, 0, 0)), Some(&4));
+        assert_eq!(d.distances.get(&pos(1, 0, 0)), None);
+    }
+
+    #[test]
+    fn recursive_backtracker_reaches_every_level() {
+        let grid = Grid3d::new(4, 5, 3).run_recursive_backtracker_algorithm();
+        let d = Dijkstra::new(pos(0, 0, 0)).run_to_completion(&grid);
+        let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
+        distance_positions.sort();
+        assert_eq!(distance_positions, grid.positions());
+    }
+}
@@ -1,25 +1,33 @@
-use std::slice::Iter;
 
-// use tuple struct for Row and Col
+// use tuple struct for Row, Col and Level
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Row(pub usize);
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Col(pub usize);
 
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Level(pub usize);
+
+// Flat grids keep everything on level 0
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct GridPos {
+    pub level: Level,
     pub row: Row,
     pub col: Col,
 }
 
 impl GridPos {
     pub fn new(row: Row, col: Col) -> Self {
-        GridPos { row: row, col: col}
+        GridPos { level: Level(0), row, col }
+    }
+
+    pub fn on_level(level: Level, row: Row, col: Col) -> Self {
+        GridPos { level, row, col }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Direction {
     North,
     East,
@@ -37,7 +45,27 @@ impl Direction {
         }
     }
 
+    pub fn turn_left(&self) -> Direction {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East  => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West  => Direction::South,
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        self.turn_left().reverse_dir()
+    }
+
     pub fn iter() -> impl Iterator<Item = Direction> {
         [Direction::North, Direction::South, Direction::East, Direction::West].iter().copied()
     }
-}
\ No newline at end of file
+}
+
+// The view of a grid Dijkstra and friends need: where the cells are and which
+// cells each one has an open passage to
+pub trait MazeGrid {
+    fn positions(&self) -> Vec<GridPos>;
+    fn links(&self, pos: GridPos) -> Vec<GridPos>;
+}
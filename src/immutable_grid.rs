@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use im::hashmap::*;
 use rand::prelude::*;
 use rand::seq::IteratorRandom;
@@ -16,7 +17,7 @@ pub struct GridCell {
 impl GridCell {
     pub fn new(pos: GridPos) -> Self {
         GridCell {
-            pos: pos,
+            pos,
             north_open: false,
             east_open: false,
             south_open: false,
@@ -34,11 +35,50 @@ impl GridCell {
     }
 }
 
+// Whether moving off one edge of the grid comes back in on the opposite edge
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WrapMode {
+    None,
+    // east and west edges meet, making a cylinder
+    Horizontal,
+    // north and south edges meet, making a cylinder on its side
+    Vertical,
+    // both pairs of edges meet, making a torus
+    Both,
+}
+
+impl Default for WrapMode {
+    fn default() -> Self {
+        WrapMode::None
+    }
+}
+
+impl WrapMode {
+    pub fn wraps_horizontally(&self) -> bool {
+        *self == WrapMode::Horizontal || *self == WrapMode::Both
+    }
+
+    pub fn wraps_vertically(&self) -> bool {
+        *self == WrapMode::Vertical || *self == WrapMode::Both
+    }
+}
+
+// A border cell with its outer wall opened, to enter or leave the maze by
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Opening {
+    pub pos: GridPos,
+    // the side of the cell that's open to the outside
+    pub dir: Direction,
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct ImmutableGrid {
     column_count: usize,
     row_count: usize,
+    wrap_mode: WrapMode,
     cells: HashMap<GridPos, GridCell>,
+    entrance: Option<Opening>,
+    exit: Option<Opening>,
 }
 
 impl ImmutableGrid {
@@ -46,24 +86,43 @@ impl ImmutableGrid {
         let grid_cells: HashMap<GridPos, GridCell> =
             (0..column_count).flat_map(|col| {
                 (0..row_count).map(move |row| {
-                    let pos = GridPos { col: Col(col), row: Row(row) };
+                    let pos = GridPos::new(Row(row), Col(col));
                     let cell = GridCell::new(pos);
                     (pos, cell)
                 })
             }).collect();
         
         ImmutableGrid {
-            column_count: column_count,
-            row_count: row_count,
+            column_count,
+            row_count,
+            wrap_mode: WrapMode::None,
             cells: grid_cells,
+            entrance: None,
+            exit: None,
         }
     }
 
+    pub fn with_wrap_mode(self, wrap_mode: WrapMode) -> Self {
+        ImmutableGrid { wrap_mode, ..self }
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    pub fn wrap_mode(&self) -> WrapMode {
+        self.wrap_mode
+    }
+
     pub fn at_northern_boundary(&self, pos: GridPos) -> bool {
-        pos.row == Row(self.row_count - 1)
+        pos.row.0 + 1 == self.row_count
     }
     pub fn at_eastern_boundary(&self, pos: GridPos) -> bool {
-        pos.col == Col(self.column_count - 1)
+        pos.col.0 + 1 == self.column_count
     }
     pub fn at_southern_boundary(&self, pos: GridPos) -> bool {
         pos.row == Row(0)
@@ -73,39 +132,58 @@ impl ImmutableGrid {
     }
 
     pub fn get_relative_cell_pos(&self, pos: GridPos, dir: Direction) -> Option<GridPos> {
+        let wraps_horizontally = self.wrap_mode.wraps_horizontally() && self.column_count > 1;
+        let wraps_vertically = self.wrap_mode.wraps_vertically() && self.row_count > 1;
+
         if self.at_southern_boundary(pos) && dir == Direction::South {
-            return None
+            return if wraps_vertically {
+                Some(GridPos { row: Row(self.row_count - 1), ..pos })
+            } else {
+                None
+            }
         }
     
         if self.at_western_boundary(pos) && dir == Direction::West {
-            return None
+            return if wraps_horizontally {
+                Some(GridPos { col: Col(self.column_count - 1), ..pos })
+            } else {
+                None
+            }
         }
     
         if self.at_northern_boundary(pos) && dir == Direction::North {
-            return None
+            return if wraps_vertically {
+                Some(GridPos { row: Row(0), ..pos })
+            } else {
+                None
+            }
         }
     
         if self.at_eastern_boundary(pos) && dir == Direction::East {
-            return None
+            return if wraps_horizontally {
+                Some(GridPos { col: Col(0), ..pos })
+            } else {
+                None
+            }
         }
     
         let pos =
             match dir {
                 Direction::North => GridPos {
-                    col: pos.col,
-                    row: Row(pos.row.0 + 1)
+                    row: Row(pos.row.0 + 1),
+                    ..pos
                 },
                 Direction::East  => GridPos {
                     col: Col(pos.col.0 + 1),
-                    row: pos.row
+                    ..pos
                 },
                 Direction::South => GridPos {
-                    col: pos.col,
-                    row: Row(pos.row.0 - 1)
+                    row: Row(pos.row.0 - 1),
+                    ..pos
                 },
                 Direction::West  => GridPos {
                     col: Col(pos.col.0 - 1),
-                    row: pos.row
+                    ..pos
                 },
             };
     
@@ -114,17 +192,16 @@ impl ImmutableGrid {
 
     fn update_cell(self, pos: GridPos, cell : GridCell) -> Self {
         ImmutableGrid { 
-            column_count: self.column_count,
-            row_count: self.row_count,
             cells: self.cells.update(pos, cell),
+            ..self
         }
     }
 
     pub fn get(&self, pos: &GridPos) -> Option<&GridCell> {
-        self.cells.get(&pos)
+        self.cells.get(pos)
     }
 
-    fn link_single(self, pos: GridPos, dir: Direction) -> Self {
+    pub fn link_single(self, pos: GridPos, dir: Direction) -> Self {
         let cell = *self.get(&pos).unwrap();
         match dir {
             Direction::North =>
@@ -138,7 +215,7 @@ impl ImmutableGrid {
         }
     }
     
-    fn link_cells(self, pos: GridPos, dir: Direction) -> Self {
+    pub fn link_cells(self, pos: GridPos, dir: Direction) -> Self {
         let grid = self.link_single(pos, dir);
         let other_cell_pos = grid.get_relative_cell_pos(pos, dir);
         match other_cell_pos {
@@ -147,11 +224,73 @@ impl ImmutableGrid {
         }
     }
 
+    pub fn unlink_cells(self, pos: GridPos, dir: Direction) -> Self {
+        let grid = self.unlink_single(pos, dir);
+        let other_cell_pos = grid.get_relative_cell_pos(pos, dir);
+        match other_cell_pos {
+            None => grid,
+            Some(pos) => grid.unlink_single(pos, dir.reverse_dir()),
+        }
+    }
+
+    fn unlink_single(self, pos: GridPos, dir: Direction) -> Self {
+        let cell = *self.get(&pos).unwrap();
+        match dir {
+            Direction::North =>
+                self.update_cell(pos, GridCell { north_open: false, ..cell } ),
+            Direction::East =>
+                self.update_cell(pos, GridCell { east_open: false,  ..cell } ),
+            Direction::South =>
+                self.update_cell(pos, GridCell { south_open: false, ..cell } ),
+            Direction::West =>
+                self.update_cell(pos, GridCell { west_open: false,  ..cell } ),
+        }
+    }
+
+    // Takes a cell out of the grid entirely, closing off any passages into it
+    pub fn remove_cell(self, pos: GridPos) -> Self {
+        let cell = match self.get(&pos) {
+            None => return self,
+            Some(&cell) => cell,
+        };
+        let grid = Direction::iter()
+            .filter(|&dir| cell.is_open_to(dir))
+            .fold(self, |grid, dir| {
+                match grid.get_relative_cell_pos(pos, dir) {
+                    Some(other) if grid.contains(other) =>
+                        grid.unlink_single(other, dir.reverse_dir()),
+                    _ => grid,
+                }
+            });
+        let is_gone = |opening: Option<Opening>| opening.filter(|opening| opening.pos != pos);
+        ImmutableGrid {
+            cells: grid.cells.without(&pos),
+            entrance: is_gone(grid.entrance),
+            exit: is_gone(grid.exit),
+            ..grid
+        }
+    }
+
+    // Whether the cell is still part of the grid, i.e. it is inside the
+    // bounds and hasn't been removed
+    pub fn contains(&self, pos: GridPos) -> bool {
+        self.cells.contains_key(&pos)
+    }
+
+    // The fraction of the grid's cells that haven't been removed
+    pub fn density(&self) -> f64 {
+        let total = self.column_count * self.row_count;
+        if total == 0 {
+            return 0.0
+        }
+        self.cells.len() as f64 / total as f64
+    }
+
     pub fn grid_rows(&self) -> Vec<(usize, Vec<GridPos>)> {
         (0..self.row_count).map(|row| {
             let row_indexes: Vec<GridPos> =
                 (0..self.column_count).map(move |col| {
-                    GridPos { col: Col(col), row: Row(row) }
+                    GridPos::new(Row(row), Col(col))
                 }).collect();
             (row, row_indexes)
         }).collect()
@@ -163,8 +302,14 @@ impl ImmutableGrid {
         positions
     }
 
+    // Binary tree and sidewinder only ever carve north and east up to the
+    // boundary, so on a wrapping grid they still make a perfect maze, they
+    // just never use the wrapped edges.
     pub fn run_binary_tree_algorithm(self) -> Self {
-        let mut rng = rand::thread_rng();
+        self.run_binary_tree_algorithm_with_rng(&mut rand::thread_rng())
+    }
+
+    pub fn run_binary_tree_algorithm_with_rng<R: Rng>(self, rng: &mut R) -> Self {
         self.positions().iter().fold(self.clone(), |grid, &pos| {
             let mut neighbors: Vec<Direction> = Vec::new();
             if !grid.at_eastern_boundary(pos) {
@@ -175,7 +320,7 @@ impl ImmutableGrid {
                 neighbors.push(Direction::North);
             } 
 
-            let dir = neighbors.iter().choose(&mut rng);
+            let dir = neighbors.iter().choose(rng);
             match dir {
                 Some(&dir) => grid.link_cells(pos, dir),
                 None => grid,
@@ -185,7 +330,10 @@ impl ImmutableGrid {
 
 
     pub fn run_sidewinder_algorithm(self) -> Self {
-        let mut rng = rand::thread_rng();
+        self.run_sidewinder_algorithm_with_rng(&mut rand::thread_rng())
+    }
+
+    pub fn run_sidewinder_algorithm_with_rng<R: Rng>(self, rng: &mut R) -> Self {
         self.grid_rows().iter().fold(self.clone(), |grid, (_, row)| {
             let mut run: Vec<GridPos> = Vec::new();
             row.iter().fold(grid, |grid, &pos| {
@@ -196,7 +344,7 @@ impl ImmutableGrid {
                     || (!grid.at_northern_boundary(pos) && rng.gen_range(0, 2) == 0);
                 
                 if should_close_out {
-                    let close_out_pos = *run.iter().choose(&mut rng).unwrap();
+                    let close_out_pos = *run.iter().choose(rng).unwrap();
                     run.clear();
                     if !grid.at_northern_boundary(pos) {
                         grid.link_cells(close_out_pos, Direction::North)
@@ -210,7 +358,242 @@ impl ImmutableGrid {
         })
     }
 
+    // The cells next to this one, whether or not there is a passage to them
+    pub fn neighbours(&self, pos: GridPos) -> Vec<(Direction, GridPos)> {
+        Direction::iter()
+            .filter_map(|dir| self.get_relative_cell_pos(pos, dir).map(|other| (dir, other)))
+            .filter(|&(_, other)| self.contains(other))
+            .collect()
+    }
+
+    // Whether the cell has exactly one passage to another cell. An entrance
+    // or exit is open to the outside, not another cell, so doesn't count.
+    pub fn is_dead_end(&self, pos: GridPos) -> bool {
+        match self.get(&pos) {
+            None => false,
+            Some(cell) => self.neighbours(pos).into_iter()
+                .filter(|&(dir, _)| cell.is_open_to(dir))
+                .count() == 1,
+        }
+    }
+
+    // Cells with exactly one passage to another cell
+    pub fn dead_ends(&self) -> Vec<GridPos> {
+        self.positions().into_iter()
+            .filter(|&pos| self.is_dead_end(pos))
+            .collect()
+    }
+
+    // Removes dead ends by linking each one, with the given probability, to
+    // a neighbour it isn't already linked to. Neighbours that are dead ends
+    // themselves are preferred, as that removes two at once. Adds loops, so
+    // the result is no longer a perfect maze.
+    pub fn braid(self, probability: f64) -> Self {
+        let mut rng = rand::thread_rng();
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(&mut rng);
+
+        dead_ends.into_iter().fold(self, |grid, pos| {
+            // linking an earlier dead end may already have fixed this one
+            let cell = *grid.get(&pos).unwrap();
+            if !grid.is_dead_end(pos) || !rng.gen_bool(probability) {
+                return grid
+            }
+
+            let candidates: Vec<(Direction, GridPos)> = grid.neighbours(pos).into_iter()
+                .filter(|&(dir, _)| !cell.is_open_to(dir))
+                .collect();
+            let dead_end_candidates: Vec<(Direction, GridPos)> = candidates.iter()
+                .filter(|&&(_, other)| grid.is_dead_end(other))
+                .copied()
+                .collect();
 
+            let choice = if dead_end_candidates.is_empty() {
+                candidates.into_iter().choose(&mut rng)
+            } else {
+                dead_end_candidates.into_iter().choose(&mut rng)
+            };
+            match choice {
+                Some((dir, _)) => grid.link_cells(pos, dir),
+                None => grid,
+            }
+        })
+    }
+
+    // Makes a sparse maze by removing every dead end, then every cell that
+    // became a dead end, and so on for the given number of passes
+    pub fn cull_dead_ends(self, passes: usize) -> Self {
+        let mut grid = self;
+        for _ in 0..passes {
+            let dead_ends = grid.dead_ends();
+            if dead_ends.is_empty() {
+                break
+            }
+            grid = dead_ends.into_iter().fold(grid, |grid, pos| grid.remove_cell(pos));
+        }
+        grid
+    }
+
+    // Removes dead ends, in random order, until no more than `density` of
+    // the grid's cells are left or there are no dead ends to remove
+    pub fn cull_to_density(self, density: f64) -> Self {
+        let mut rng = rand::thread_rng();
+        let mut grid = self;
+        while grid.density() > density {
+            let mut dead_ends = grid.dead_ends();
+            if dead_ends.is_empty() {
+                break
+            }
+            dead_ends.shuffle(&mut rng);
+            for pos in dead_ends {
+                if grid.density() <= density {
+                    break
+                }
+                // removing a neighbour can leave this cell with no links
+                if grid.is_dead_end(pos) {
+                    grid = grid.remove_cell(pos);
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn entrance(&self) -> Option<Opening> {
+        self.entrance
+    }
+
+    pub fn exit(&self) -> Option<Opening> {
+        self.exit
+    }
+
+    // Whether the cell's wall in that direction is the entrance or exit
+    pub fn is_opening(&self, pos: GridPos, dir: Direction) -> bool {
+        [self.entrance, self.exit].contains(&Some(Opening { pos, dir }))
+    }
+
+    // The sides of a cell on the outside of the grid, which wrapped edges
+    // don't have
+    pub fn border_sides(&self, pos: GridPos) -> Vec<Direction> {
+        Direction::iter()
+            .filter(|&dir| self.get_relative_cell_pos(pos, dir).is_none())
+            .collect()
+    }
+
+    // The cells with a side on the outside of the grid
+    pub fn border_cells(&self) -> Vec<GridPos> {
+        self.positions().into_iter()
+            .filter(|&pos| !self.border_sides(pos).is_empty())
+            .collect()
+    }
+
+    // Closes the entrance and exit, if there are any
+    pub fn without_openings(self) -> Self {
+        let grid = [self.entrance, self.exit].iter().flatten()
+            .fold(self.clone(), |grid, opening| grid.unlink_single(opening.pos, opening.dir));
+        ImmutableGrid { entrance: None, exit: None, ..grid }
+    }
+
+    // Opens an entrance and an exit in the outer wall of two border cells,
+    // closing any there were before. The openings module chooses the cells.
+    // None if either cell has no outer wall, e.g. it's inside the grid, has
+    // been removed or the grid is a torus.
+    pub fn with_openings(self, entrance: GridPos, exit: GridPos) -> Option<Self> {
+        // entrances prefer the west and south walls and exits the east and
+        // north, so a cell that has to be both gets two different gaps
+        let side = |pos: GridPos, preference: [Direction; 4]| {
+            if !self.contains(pos) {
+                return None
+            }
+            let sides = self.border_sides(pos);
+            preference.iter().find(|dir| sides.contains(dir)).copied()
+        };
+        let entrance = Opening {
+            pos: entrance,
+            dir: side(entrance, [Direction::West, Direction::South, Direction::East, Direction::North])?,
+        };
+        let exit = Opening {
+            pos: exit,
+            dir: side(exit, [Direction::East, Direction::North, Direction::West, Direction::South])?,
+        };
+
+        let grid = self.without_openings()
+            .link_single(entrance.pos, entrance.dir)
+            .link_single(exit.pos, exit.dir);
+        Some(ImmutableGrid { entrance: Some(entrance), exit: Some(exit), ..grid })
+    }
+
+    // Carves using get_relative_cell_pos, so passages will cross the wrapped
+    // edges of a cylinder or torus
+    pub fn run_recursive_backtracker_algorithm(self) -> Self {
+        self.run_recursive_backtracker_algorithm_with_rng(&mut rand::thread_rng())
+    }
+
+    pub fn run_recursive_backtracker_algorithm_with_rng<R: Rng>(self, rng: &mut R) -> Self {
+        let start = match self.positions().into_iter().choose(rng) {
+            None => return self,
+            Some(pos) => pos,
+        };
+
+        let mut grid = self;
+        let mut visited: HashSet<GridPos> = HashSet::new();
+        visited.insert(start);
+        let mut stack: Vec<GridPos> = vec![start];
+        while let Some(&pos) = stack.last() {
+            let next = grid.neighbours(pos).into_iter()
+                .filter(|(_, other)| !visited.contains(other))
+                .choose(rng);
+            match next {
+                None => { stack.pop(); },
+                Some((dir, other)) => {
+                    grid = grid.link_cells(pos, dir);
+                    visited.insert(other);
+                    stack.push(other);
+                }
+            }
+        }
+        grid
+    }
+
+    // A random walk that carves a passage whenever it steps into a cell it
+    // hasn't been to before. Slow to finish, but every spanning tree is
+    // equally likely. The cells must all be reachable from each other.
+    pub fn run_aldous_broder_algorithm(self) -> Self {
+        self.run_aldous_broder_algorithm_with_rng(&mut rand::thread_rng())
+    }
+
+    pub fn run_aldous_broder_algorithm_with_rng<R: Rng>(self, rng: &mut R) -> Self {
+        let positions = self.positions();
+        let mut pos = match positions.iter().choose(rng) {
+            None => return self,
+            Some(&pos) => pos,
+        };
+
+        let mut grid = self;
+        let mut visited: HashSet<GridPos> = HashSet::new();
+        visited.insert(pos);
+        while visited.len() < positions.len() {
+            let (dir, other) = grid.neighbours(pos).into_iter().choose(rng).unwrap();
+            if visited.insert(other) {
+                grid = grid.link_cells(pos, dir);
+            }
+            pos = other;
+        }
+        grid
+    }
+}
+
+impl MazeGrid for ImmutableGrid {
+    fn positions(&self) -> Vec<GridPos> {
+        ImmutableGrid::positions(self)
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        let cell = self.get(&pos).unwrap();
+        Direction::iter()
+            .filter(|&dir| cell.is_open_to(dir))
+            .filter_map(|dir| self.get_relative_cell_pos(pos, dir))
+            .collect()
+    }
 }
 
 // **************************
@@ -234,9 +617,115 @@ impl Iterator for Iter {
     type Item = GridCell;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.cell_iter.next() {
-            None => None,
-            Some((_, cell)) => Some(cell)
-        }
+        self.cell_iter.next().map(|(_, cell)| cell)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::dijkstra::*;
+    use super::{ImmutableGrid, WrapMode};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn unwrapped_grid_has_no_cells_past_the_edges() {
+        let grid = ImmutableGrid::new(3, 2);
+        assert_eq!(grid.get_relative_cell_pos(pos(0, 2), Direction::East), None);
+        assert_eq!(grid.get_relative_cell_pos(pos(1, 0), Direction::North), None);
+    }
+
+    #[test]
+    fn cylinder_wraps_east_west_only() {
+        let grid = ImmutableGrid::new(3, 2).with_wrap_mode(WrapMode::Horizontal);
+        assert_eq!(grid.get_relative_cell_pos(pos(1, 2), Direction::East), Some(pos(1, 0)));
+        assert_eq!(grid.get_relative_cell_pos(pos(1, 0), Direction::West), Some(pos(1, 2)));
+        assert_eq!(grid.get_relative_cell_pos(pos(1, 0), Direction::North), None);
+    }
+
+    #[test]
+    fn torus_wraps_both_ways() {
+        let grid = ImmutableGrid::new(3, 2).with_wrap_mode(WrapMode::Both);
+        assert_eq!(grid.get_relative_cell_pos(pos(1, 1), Direction::North), Some(pos(0, 1)));
+        assert_eq!(grid.get_relative_cell_pos(pos(0, 1), Direction::South), Some(pos(1, 1)));
+        assert_eq!(grid.get_relative_cell_pos(pos(0, 0), Direction::West), Some(pos(0, 2)));
+    }
+
+    #[test]
+    fn braiding_everything_removes_all_dead_ends() {
+        let grid = ImmutableGrid::new(10, 8).run_sidewinder_algorithm();
+        assert!(!grid.dead_ends().is_empty());
+        let grid = grid.braid(1.0);
+        assert!(grid.dead_ends().is_empty());
+    }
+
+    #[test]
+    fn openings_dont_stop_a_cell_being_a_dead_end() {
+        // a corridor along the bottom row, open at both ends
+        let grid = (0..3).fold(ImmutableGrid::new(4, 1), |grid, col| grid.link_cells(pos(0, col), Direction::East));
+        let opened = grid.clone().with_openings(pos(0, 0), pos(0, 3)).unwrap();
+        assert_eq!(opened.dead_ends(), grid.dead_ends());
+        assert_eq!(opened.dead_ends(), vec![pos(0, 0), pos(0, 3)]);
+        assert_eq!(opened.cull_dead_ends(1).positions(), vec![pos(0, 1), pos(0, 2)]);
+    }
+
+    #[test]
+    fn braiding_nothing_leaves_the_maze_alone() {
+        let grid = ImmutableGrid::new(10, 8).run_sidewinder_algorithm();
+        let dead_ends = grid.dead_ends();
+        assert_eq!(grid.braid(0.0).dead_ends(), dead_ends);
+    }
+
+    #[test]
+    fn removing_a_cell_closes_the_passages_into_it() {
+        let grid = ImmutableGrid::new(3, 1)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::East)
+            .remove_cell(pos(0, 2));
+        assert!(!grid.contains(pos(0, 2)));
+        assert!(!grid.get(&pos(0, 1)).unwrap().is_open_to(Direction::East));
+        assert_eq!(grid.positions(), vec![pos(0, 0), pos(0, 1)]);
+    }
+
+    #[test]
+    fn culling_one_pass_removes_the_current_dead_ends() {
+        let grid = ImmutableGrid::new(10, 8).run_sidewinder_algorithm();
+        let dead_ends = grid.dead_ends();
+        let culled = grid.cull_dead_ends(1);
+        assert_eq!(culled.positions().len(), 10 * 8 - dead_ends.len());
+        assert!(dead_ends.iter().all(|&pos| !culled.contains(pos)));
+        assert!(culled.positions().iter().all(|&pos| {
+            culled.links(pos).into_iter().all(|other| culled.contains(other))
+        }));
+    }
+
+    #[test]
+    fn culling_to_density_stops_at_the_target() {
+        let grid = ImmutableGrid::new(10, 10)
+            .run_recursive_backtracker_algorithm()
+            .cull_to_density(0.4);
+        assert_eq!(grid.positions().len(), 40);
+    }
+
+    #[test]
+    fn linking_across_a_wrapped_edge_opens_both_sides() {
+        let grid = ImmutableGrid::new(3, 2)
+            .with_wrap_mode(WrapMode::Horizontal)
+            .link_cells(pos(0, 2), Direction::East);
+        assert!(grid.get(&pos(0, 2)).unwrap().is_open_to(Direction::East));
+        assert!(grid.get(&pos(0, 0)).unwrap().is_open_to(Direction::West));
+        assert_eq!(grid.links(pos(0, 0)), vec![pos(0, 2)]);
+    }
+
+    #[test]
+    fn aldous_broder_makes_a_spanning_tree() {
+        let grid = ImmutableGrid::new(8, 6).run_aldous_broder_algorithm();
+        let link_count: usize = grid.positions().iter().map(|&p| grid.links(p).len()).sum();
+        assert_eq!(link_count / 2, 8 * 6 - 1);
+        let d = Dijkstra::new(pos(0, 0)).run_to_completion(&grid);
+        assert_eq!(d.distances.size(), 8 * 6);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::grid_primitives::*;
+use super::dijkstra::*;
+
+// Locked doors and their keys for a perfect maze. The goal is the cell
+// furthest from the start, and the doors are spread along the way there so
+// it can't be reached without opening them all. Each door's key goes in the
+// part of the maze that opening the door before it lets the player into,
+// as deep into a side branch as that part has, so the doors open in order
+// and every key can be reached before the door it opens.
+
+// A door on the passage between `door` and `behind`, with `behind` the side
+// further from the start, and where its key is
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Lock {
+    pub door: GridPos,
+    pub behind: GridPos,
+    pub key: GridPos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    pub start: GridPos,
+    pub goal: GridPos,
+    // in the order they're opened
+    pub locks: Vec<Lock>,
+}
+
+// Fewer than `count` locks if the way to the goal has fewer passages
+pub fn place_locks<G: MazeGrid + ?Sized>(grid: &G, start: GridPos, count: usize) -> Puzzle {
+    let from_start = Dijkstra::new(start).run_to_completion(grid);
+    let (goal, _) = from_start.furthest();
+    let path = from_start.path_to(goal, grid);
+
+    let passages = path.len().saturating_sub(1);
+    let mut doors: Vec<(GridPos, GridPos)> = (1..=count)
+        .filter(|_| passages > 0)
+        .map(|i| i * passages / (count + 1))
+        .map(|index| (path[index], path[index + 1]))
+        .collect();
+    doors.dedup();
+
+    // how far each cell is down a side branch off the way to the goal
+    let from_path = Dijkstra::from_roots(&path).run_to_completion(grid);
+    let mut reached: HashSet<GridPos> = HashSet::new();
+    let locks = doors.iter().enumerate()
+        .map(|(i, &(door, behind))| {
+            // with the doors before this one open. Only a maze with loops
+            // can have nothing new, and there the start will do.
+            let reachable = reachable(grid, start, &doors[i..]);
+            let key = reachable.iter()
+                .filter(|pos| !reached.contains(pos))
+                .max_by_key(|&&pos| (from_path.distances[&pos], pos))
+                .copied()
+                .unwrap_or(start);
+            reached = reachable;
+            Lock { door, behind, key }
+        })
+        .collect();
+
+    Puzzle { start, goal, locks }
+}
+
+// Collects every key it can reach and opens every door it has the key for
+// until it's stuck, then checks whether it got to the goal
+pub fn is_solvable<G: MazeGrid + ?Sized>(grid: &G, puzzle: &Puzzle) -> bool {
+    let mut locked: Vec<Lock> = puzzle.locks.clone();
+    loop {
+        let doors: Vec<(GridPos, GridPos)> = locked.iter().map(|lock| (lock.door, lock.behind)).collect();
+        let reachable = reachable(grid, puzzle.start, &doors);
+        let still_locked: Vec<Lock> = locked.iter()
+            .filter(|lock| !reachable.contains(&lock.key))
+            .copied()
+            .collect();
+        if still_locked.len() == locked.len() {
+            return reachable.contains(&puzzle.goal)
+        }
+        locked = still_locked;
+    }
+}
+
+// The cells that can be reached from the start without going through any of
+// the doors
+fn reachable<G: MazeGrid + ?Sized>(grid: &G, start: GridPos, doors: &[(GridPos, GridPos)]) -> HashSet<GridPos> {
+    let locked = Locked { grid, doors };
+    Dijkstra::new(start).run_to_completion(&locked).distances.keys().copied().collect()
+}
+
+// The grid with the passages through the doors closed
+struct Locked<'a, G: MazeGrid + ?Sized> {
+    grid: &'a G,
+    doors: &'a [(GridPos, GridPos)],
+}
+
+impl<'a, G: MazeGrid + ?Sized> MazeGrid for Locked<'a, G> {
+    fn positions(&self) -> Vec<GridPos> {
+        self.grid.positions()
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        self.grid.links(pos).into_iter()
+            .filter(|&other| !self.doors.contains(&(pos, other)) && !self.doors.contains(&(other, pos)))
+            .collect()
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "from {:?} to {:?}", self.start, self.goal)?;
+        self.locks.iter().enumerate().try_for_each(|(i, lock)| {
+            write!(f, "\ndoor {} between {:?} and {:?}, key at {:?}", i + 1, lock.door, lock.behind, lock.key)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::test_support::*;
+    use super::{place_locks, is_solvable, reachable};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn locks_open_in_order() {
+        seeded(10).for_each(|(seed, mut rng)| {
+            let grid = ImmutableGrid::new(10, 10).run_recursive_backtracker_algorithm_with_rng(&mut rng);
+            let puzzle = place_locks(&grid, pos(0, 0), 4);
+            assert_eq!(puzzle.locks.len(), 4);
+            assert!(is_solvable(&grid, &puzzle), "seed {}", seed);
+
+            // every key is behind the door before it, and the goal behind
+            // the last
+            let doors: Vec<(GridPos, GridPos)> = puzzle.locks.iter().map(|lock| (lock.door, lock.behind)).collect();
+            puzzle.locks.iter().enumerate().skip(1).for_each(|(i, lock)| {
+                assert!(!reachable(&grid, pos(0, 0), &doors[i - 1..]).contains(&lock.key), "seed {}", seed);
+            });
+            assert!(!reachable(&grid, pos(0, 0), &doors[3..]).contains(&puzzle.goal), "seed {}", seed);
+        });
+    }
+
+    #[test]
+    fn keys_behind_their_own_doors_cant_be_solved() {
+        seeded(10).for_each(|(seed, mut rng)| {
+            let grid = ImmutableGrid::new(8, 8).run_recursive_backtracker_algorithm_with_rng(&mut rng);
+            let mut puzzle = place_locks(&grid, pos(0, 0), 2);
+            let (first, second) = (puzzle.locks[0].key, puzzle.locks[1].key);
+            puzzle.locks[0].key = second;
+            puzzle.locks[1].key = first;
+            assert!(!is_solvable(&grid, &puzzle), "seed {}", seed);
+        });
+    }
+
+    #[test]
+    fn short_mazes_get_fewer_locks() {
+        let grid = ImmutableGrid::new(3, 1)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::East);
+        let puzzle = place_locks(&grid, pos(0, 0), 5);
+        assert_eq!(puzzle.goal, pos(0, 2));
+        assert_eq!(puzzle.locks.len(), 2);
+        assert!(is_solvable(&grid, &puzzle));
+    }
+}
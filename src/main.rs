@@ -4,17 +4,55 @@ extern crate chrono;
 
 use piston_window::*;
 use chrono::prelude::*;
+use rand::Rng;
+use rand::seq::IteratorRandom;
 
 pub mod grid_primitives;
 pub mod immutable_grid;
+pub mod openings;
 pub mod mutable_linked_grid;
 pub mod dijkstra;
+pub mod upsilon_grid;
+pub mod weave_grid;
+pub mod grid_3d;
+pub mod cube_grid;
+pub mod sphere_grid;
+pub mod dungeon;
+pub mod disjoint_set;
+pub mod weighted_dijkstra;
+pub mod astar;
+pub mod solvers;
+pub mod flat_dijkstra;
+pub mod bench;
+pub mod analysis;
+pub mod compare;
+pub mod validation;
+pub mod uniformity;
+pub mod difficulty;
+pub mod waypoints;
+pub mod solution_length;
+pub mod keys_and_doors;
+#[cfg(test)]
+mod test_support;
 
 use grid_primitives::*;
 use immutable_grid::*;
-use mutable_linked_grid::*;
+use openings::*;
 use dijkstra::*;
+use upsilon_grid::*;
+use weave_grid::*;
+use grid_3d::*;
+use cube_grid::*;
+use sphere_grid::*;
+use dungeon::*;
+use weighted_dijkstra::*;
+use astar::*;
+use solvers::*;
+use waypoints::*;
+use solution_length::*;
+use keys_and_doors::*;
 
+const TITLE: &str = "Mazes for Programmers - Chapter 3!";
 const ROWS: usize = 70;
 const COLUMNS: usize = 70;
 
@@ -23,6 +61,115 @@ const DRAW_CELL_SIZE: f64 = 11.0;
 const FULL_DRAW_WIDTH: f64 = DRAW_CELL_SIZE * COLUMNS as f64;
 const FULL_DRAW_HEIGHT: f64 = DRAW_CELL_SIZE * ROWS as f64;
 const DIJKSTRA_SPEED: i64 = 30; // smaller is faster
+const ASTAR_SPEED: i64 = 5; // milliseconds per expanded cell
+const SOLVER_SPEED: i64 = 15; // milliseconds per solver step
+const BRAID_PROBABILITY: f64 = 0.5;
+const SPARSE_DENSITY: f64 = 0.5;
+const LAVA_PROBABILITY: f64 = 0.1;
+const LAVA_WEIGHT: u32 = 50;
+const SPAWN_POINTS: usize = 6;
+const WAYPOINTS: usize = 3;
+const SOLUTION_LENGTH: std::ops::RangeInclusive<usize> = 400..=450;
+const LENGTH_ATTEMPTS: usize = 3;
+const LENGTH_EDITS: usize = 100;
+const LOCKS: usize = 4;
+
+// 3D mazes draw their levels side by side, separated by a gap
+const LEVELS_3D: usize = 3;
+const LEVEL_GAP_3D: usize = 2;
+const LEVEL_COLUMNS_3D: usize = (COLUMNS - LEVEL_GAP_3D * (LEVELS_3D - 1)) / LEVELS_3D;
+
+// the cube net is four faces wide
+const CUBE_SIZE: usize = COLUMNS / 4;
+
+// sphere mazes draw each hemisphere as a disc, side by side
+const SPHERE_RINGS: usize = 16;
+const SPHERE_RADIUS: f64 = FULL_DRAW_WIDTH / 4.0 - DRAW_CELL_SIZE;
+
+// Everything drawn over the maze, cleared whenever the maze changes
+#[derive(Default)]
+struct Overlays {
+    dijkstra: Option<Dijkstra>,
+    // no start time shows the whole of the flood at once
+    dijkstra_start_time: Option<DateTime<Utc>>,
+    // the log the flood is being stepped through by hand, and which step
+    // it's on
+    dijkstra_log: Option<(DijkstraLog, usize)>,
+    path: Option<Vec<GridPos>>,
+    weights: Option<Weights>,
+    astar: Option<AStar>,
+    astar_start_time: Option<DateTime<Utc>>,
+    territories: Option<Dijkstra>,
+    solution: Option<Solution>,
+    solution_start_time: Option<DateTime<Utc>>,
+    puzzle: Option<Puzzle>,
+    // what the last key found out, shown in the window title
+    status: Option<String>,
+}
+
+// The kinds of maze the viewer can display
+enum Maze {
+    Standard(ImmutableGrid),
+    Upsilon(UpsilonGrid),
+    Weave(WeaveGrid),
+    ThreeD(Grid3d),
+    Cube(CubeGrid),
+    Sphere(SphereGrid),
+    Dungeon(Dungeon),
+}
+
+impl Maze {
+    fn grid(&self) -> &dyn MazeGrid {
+        match self {
+            Maze::Standard(grid) => grid,
+            Maze::Upsilon(grid) => grid,
+            Maze::Weave(grid) => grid,
+            Maze::ThreeD(grid) => grid,
+            Maze::Cube(grid) => grid,
+            Maze::Sphere(grid) => grid,
+            Maze::Dungeon(dungeon) => &dungeon.grid,
+        }
+    }
+
+    // Whether the rows, columns and levels of a cell say how far it is from
+    // another, which is what the A* heuristics guess from. Wrapped edges,
+    // diagonals, passages under crossings and folded faces all make them
+    // guess too much.
+    fn is_flat(&self) -> bool {
+        match self {
+            Maze::Standard(grid) => grid.wrap_mode() == WrapMode::None,
+            Maze::Dungeon(_) | Maze::ThreeD(_) => true,
+            _ => false,
+        }
+    }
+
+    // The square grid underneath, for the mazes that have one
+    fn standard_grid(&self) -> Option<&ImmutableGrid> {
+        match self {
+            Maze::Standard(grid) => Some(grid),
+            Maze::Dungeon(dungeon) => Some(&dungeon.grid),
+            _ => None,
+        }
+    }
+
+    fn cell_centre(&self, pos: GridPos) -> [f64; 2] {
+        let points: Vec<[f64; 2]> = self.cell_polygons(pos).into_iter().flatten().collect();
+        let count = points.len() as f64;
+        let (x, y) = points.iter().fold((0.0, 0.0), |(x, y), point| (x + point[0], y + point[1]));
+        [x / count, y / count]
+    }
+
+    // The area a cell covers on screen, as convex polygons
+    fn cell_polygons(&self, pos: GridPos) -> Vec<Vec<[f64; 2]>> {
+        let (x1, y1) = match self {
+            Maze::Cube(grid) => cube_cell_origin(grid, pos),
+            Maze::Sphere(grid) => return sphere_cell_polygons(grid, pos),
+            _ => cell_origin(pos),
+        };
+        let (x2, y2) = (x1 + DRAW_CELL_SIZE, y1 + DRAW_CELL_SIZE);
+        vec![vec![[x1, y1], [x2, y1], [x2, y2], [x1, y2]]]
+    }
+}
 
 fn render_grid<G, T>(grid: &ImmutableGrid, context: &Context, graphics: &mut G)
         where G: Graphics<Texture = T>, T: ImageSize {
@@ -55,7 +202,18 @@ fn render_grid<G, T>(grid: &ImmutableGrid, context: &Context, graphics: &mut G)
                 context.transform, graphics);
         }
 
-        if grid.at_eastern_boundary(pos) {
+        // cells only draw their east and south walls where there's no cell
+        // on the other side to draw it, which is at the boundary or next to
+        // a removed cell. On a wrapping grid the outer walls can be open to
+        // the opposite edge, and the entrance and exit leave a gap in them.
+        let east_is_empty = grid.at_eastern_boundary(pos)
+            || grid.get_relative_cell_pos(pos, Direction::East)
+                   .map_or(true, |other| !grid.contains(other));
+        let south_is_empty = grid.at_southern_boundary(pos)
+            || grid.get_relative_cell_pos(pos, Direction::South)
+                   .map_or(true, |other| !grid.contains(other));
+
+        if east_is_empty && !cell.is_open_to(Direction::East) {
             // draw right line
             line_from_to(
                 black, 1.0,
@@ -63,7 +221,7 @@ fn render_grid<G, T>(grid: &ImmutableGrid, context: &Context, graphics: &mut G)
                 context.transform, graphics);
         }
 
-        if grid.at_southern_boundary(pos) {
+        if south_is_empty && !cell.is_open_to(Direction::South) {
             // draw bottom line
             line_from_to(
                 black, 1.0,
@@ -73,6 +231,465 @@ fn render_grid<G, T>(grid: &ImmutableGrid, context: &Context, graphics: &mut G)
     });
 }
 
+// The top-left corner of a cell on screen
+fn cell_origin(pos: GridPos) -> (f64, f64) {
+    let col = pos.level.0 * (LEVEL_COLUMNS_3D + LEVEL_GAP_3D) + pos.col.0;
+    let x1 = col as f64 * DRAW_CELL_SIZE + DRAW_PADDING;
+    // Note: row 0 should be at the bottom
+    let y1 = (ROWS - 1 - pos.row.0) as f64 * DRAW_CELL_SIZE + DRAW_PADDING;
+    (x1, y1)
+}
+
+fn render_upsilon_grid<G, T>(grid: &UpsilonGrid, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let black = [0.0, 0.0, 0.0, 100.0];
+
+    // Octagons spill over their cell by `inset` on every side so that
+    // diagonal octagons share a side, squares shrink by the same amount.
+    // This ratio makes the octagons regular.
+    let inset = DRAW_CELL_SIZE / (2.0 + 2.0 * std::f64::consts::SQRT_2);
+
+    grid.iter().for_each(|cell| {
+        let (x1, y1) = cell_origin(cell.pos);
+        let x2 = x1 + DRAW_CELL_SIZE;
+        let y2 = y1 + DRAW_CELL_SIZE;
+
+        let sides: Vec<(UpsilonDirection, [f64; 2], [f64; 2])> =
+            match grid.shape_of(cell.pos) {
+                UpsilonShape::Octagon => {
+                    let (left, right) = (x1 - inset, x2 + inset);
+                    let (top, bottom) = (y1 - inset, y2 + inset);
+                    vec![
+                        (UpsilonDirection::North,     [x1 + inset, top],    [x2 - inset, top]),
+                        (UpsilonDirection::NorthEast, [x2 - inset, top],    [right, y1 + inset]),
+                        (UpsilonDirection::East,      [right, y1 + inset],  [right, y2 - inset]),
+                        (UpsilonDirection::SouthEast, [right, y2 - inset],  [x2 - inset, bottom]),
+                        (UpsilonDirection::South,     [x2 - inset, bottom], [x1 + inset, bottom]),
+                        (UpsilonDirection::SouthWest, [x1 + inset, bottom], [left, y2 - inset]),
+                        (UpsilonDirection::West,      [left, y2 - inset],   [left, y1 + inset]),
+                        (UpsilonDirection::NorthWest, [left, y1 + inset],   [x1 + inset, top]),
+                    ]
+                },
+                UpsilonShape::Square => {
+                    let (left, right) = (x1 + inset, x2 - inset);
+                    let (top, bottom) = (y1 + inset, y2 - inset);
+                    vec![
+                        (UpsilonDirection::North, [left, top],     [right, top]),
+                        (UpsilonDirection::East,  [right, top],    [right, bottom]),
+                        (UpsilonDirection::South, [right, bottom], [left, bottom]),
+                        (UpsilonDirection::West,  [left, bottom],  [left, top]),
+                    ]
+                },
+            };
+
+        sides.into_iter()
+            .filter(|&(dir, _, _)| !cell.is_open_to(dir))
+            .for_each(|(_, from, to)| {
+                line_from_to(black, 1.0, from, to, context.transform, graphics);
+            });
+    });
+}
+
+fn render_weave_grid<G, T>(grid: &WeaveGrid, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let black = [0.0, 0.0, 0.0, 100.0];
+    let inset = DRAW_CELL_SIZE * 0.15;
+
+    // Cells are drawn inset from their square, with a short corridor out to
+    // the edge on each open side. The passage under a crossing is just the
+    // corridors either side of the over cell, which leaves the gaps.
+    let corridor = |dir: Direction, x: [f64; 4], y: [f64; 4]| -> Vec<([f64; 2], [f64; 2])> {
+        let [x1, x2, x3, x4] = x;
+        let [y1, y2, y3, y4] = y;
+        match dir {
+            Direction::North => vec![([x2, y1], [x2, y2]), ([x3, y1], [x3, y2])],
+            Direction::South => vec![([x2, y3], [x2, y4]), ([x3, y3], [x3, y4])],
+            Direction::West  => vec![([x1, y2], [x2, y2]), ([x1, y3], [x2, y3])],
+            Direction::East  => vec![([x3, y2], [x4, y2]), ([x3, y3], [x4, y3])],
+        }
+    };
+    let wall = |dir: Direction, x: [f64; 4], y: [f64; 4]| -> ([f64; 2], [f64; 2]) {
+        let [_, x2, x3, _] = x;
+        let [_, y2, y3, _] = y;
+        match dir {
+            Direction::North => ([x2, y2], [x3, y2]),
+            Direction::South => ([x2, y3], [x3, y3]),
+            Direction::West  => ([x2, y2], [x2, y3]),
+            Direction::East  => ([x3, y2], [x3, y3]),
+        }
+    };
+
+    grid.over_grid().iter().for_each(|cell| {
+        let (x1, y1) = cell_origin(cell.pos);
+        let x = [x1, x1 + inset, x1 + DRAW_CELL_SIZE - inset, x1 + DRAW_CELL_SIZE];
+        let y = [y1, y1 + inset, y1 + DRAW_CELL_SIZE - inset, y1 + DRAW_CELL_SIZE];
+
+        let mut lines: Vec<([f64; 2], [f64; 2])> = Vec::new();
+        Direction::iter().for_each(|dir| {
+            if cell.is_open_to(dir) {
+                lines.extend(corridor(dir, x, y));
+            } else {
+                lines.push(wall(dir, x, y));
+            }
+        });
+
+        match grid.under_cell(cell.pos) {
+            Some(Orientation::Horizontal) => {
+                lines.extend(corridor(Direction::West, x, y));
+                lines.extend(corridor(Direction::East, x, y));
+            },
+            Some(Orientation::Vertical) => {
+                lines.extend(corridor(Direction::North, x, y));
+                lines.extend(corridor(Direction::South, x, y));
+            },
+            None => (),
+        }
+
+        lines.into_iter().for_each(|(from, to)| {
+            line_from_to(black, 1.0, from, to, context.transform, graphics);
+        });
+    });
+}
+
+fn render_grid_3d<G, T>(grid: &Grid3d, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let black = [0.0, 0.0, 0.0, 100.0];
+    let stairs_up = [0.8, 0.1, 0.1, 1.0];
+    let stairs_down = [0.1, 0.1, 0.8, 1.0];
+
+    grid.iter().for_each(|cell| {
+        let (x1, y1) = cell_origin(cell.pos);
+        let x2 = x1 + DRAW_CELL_SIZE;
+        let y2 = y1 + DRAW_CELL_SIZE;
+
+        // every cell draws all of its closed walls, so there are no
+        // boundary special cases
+        let walls = [
+            (Direction3d::North, [x1, y1], [x2, y1]),
+            (Direction3d::East,  [x2, y1], [x2, y2]),
+            (Direction3d::South, [x1, y2], [x2, y2]),
+            (Direction3d::West,  [x1, y1], [x1, y2]),
+        ];
+        walls.iter()
+            .filter(|&&(dir, _, _)| !cell.is_open_to(dir))
+            .for_each(|&(_, from, to)| {
+                line_from_to(black, 1.0, from, to, context.transform, graphics);
+            });
+
+        // stairs are a small triangle pointing the way they go, up stairs
+        // on the right of the cell and down stairs on the left
+        let third = DRAW_CELL_SIZE / 3.0;
+        if cell.up_open {
+            let points = [[x2 - third, y2 - 2.0], [x2 - 2.0, y2 - 2.0], [x2 - third / 2.0 - 2.0, y1 + 2.0]];
+            polygon(stairs_up, &points, context.transform, graphics);
+        }
+        if cell.down_open {
+            let points = [[x1 + 2.0, y1 + 2.0], [x1 + third, y1 + 2.0], [x1 + third / 2.0 + 2.0, y2 - 2.0]];
+            polygon(stairs_down, &points, context.transform, graphics);
+        }
+    });
+}
+
+// Faces are placed in the net with their rows and columns continuing on
+// from the faces next to them
+fn cube_cell_origin(grid: &CubeGrid, pos: GridPos) -> (f64, f64) {
+    let (net_col, net_row) = grid.net_position(pos.level);
+    let col = net_col * grid.size() + pos.col.0;
+    let row = net_row * grid.size() + pos.row.0;
+    let x1 = col as f64 * DRAW_CELL_SIZE + DRAW_PADDING;
+    let y1 = (ROWS - 1 - row) as f64 * DRAW_CELL_SIZE + DRAW_PADDING;
+    (x1, y1)
+}
+
+fn render_cube_net<G, T>(grid: &CubeGrid, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let black = [0.0, 0.0, 0.0, 100.0];
+
+    // Every cell draws all of its closed walls. Around the outside of the
+    // net an open wall is a passage onto the face it folds against, and
+    // that face leaves a matching gap.
+    grid.iter().for_each(|cell| {
+        let (x1, y1) = cube_cell_origin(grid, cell.pos);
+        let x2 = x1 + DRAW_CELL_SIZE;
+        let y2 = y1 + DRAW_CELL_SIZE;
+
+        let walls = [
+            (Direction::North, [x1, y1], [x2, y1]),
+            (Direction::East,  [x2, y1], [x2, y2]),
+            (Direction::South, [x1, y2], [x2, y2]),
+            (Direction::West,  [x1, y1], [x1, y2]),
+        ];
+        walls.iter()
+            .filter(|&&(dir, _, _)| !cell.is_open_to(dir))
+            .for_each(|&(_, from, to)| {
+                line_from_to(black, 1.0, from, to, context.transform, graphics);
+            });
+    });
+}
+
+// A point on a hemisphere's disc, `radius` from the pole out to the equator
+// at 1.0, and `turn` of the way round (0.0 to 1.0)
+fn sphere_point(hemisphere: Level, radius: f64, turn: f64) -> [f64; 2] {
+    let centre_x = DRAW_PADDING + FULL_DRAW_WIDTH / 4.0 + hemisphere.0 as f64 * FULL_DRAW_WIDTH / 2.0;
+    let centre_y = DRAW_PADDING + FULL_DRAW_HEIGHT / 2.0;
+    let angle = turn * 2.0 * std::f64::consts::PI;
+    [centre_x + radius * SPHERE_RADIUS * angle.cos(),
+     centre_y + radius * SPHERE_RADIUS * angle.sin()]
+}
+
+// The arc between two turns, split into short straight lines
+fn sphere_arc(hemisphere: Level, radius: f64, from_turn: f64, to_turn: f64) -> Vec<[f64; 2]> {
+    let steps = (((to_turn - from_turn) * 64.0).ceil() as usize).max(1);
+    (0..=steps).map(|step| {
+        let turn = from_turn + (to_turn - from_turn) * step as f64 / steps as f64;
+        sphere_point(hemisphere, radius, turn)
+    }).collect()
+}
+
+fn sphere_cell_bounds(grid: &SphereGrid, pos: GridPos) -> (f64, f64, f64, f64) {
+    let rings = grid.ring_count() as f64;
+    let size = grid.ring_size(pos.row) as f64;
+    (pos.row.0 as f64 / rings, (pos.row.0 + 1) as f64 / rings,
+     pos.col.0 as f64 / size, (pos.col.0 + 1) as f64 / size)
+}
+
+fn sphere_cell_polygons(grid: &SphereGrid, pos: GridPos) -> Vec<Vec<[f64; 2]>> {
+    let (inner, outer, from_turn, to_turn) = sphere_cell_bounds(grid, pos);
+    let inner_arc = sphere_arc(pos.level, inner, from_turn, to_turn);
+    let outer_arc = sphere_arc(pos.level, outer, from_turn, to_turn);
+    // one quad per step of the arcs keeps every polygon convex
+    (0..inner_arc.len() - 1).map(|i| {
+        vec![inner_arc[i], outer_arc[i], outer_arc[i + 1], inner_arc[i + 1]]
+    }).collect()
+}
+
+fn render_sphere_grid<G, T>(grid: &SphereGrid, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let black = [0.0, 0.0, 0.0, 100.0];
+
+    grid.positions().into_iter().for_each(|pos| {
+        let cell = grid.get(&pos).unwrap();
+        let (inner, outer, from_turn, to_turn) = sphere_cell_bounds(grid, pos);
+        let mut walls: Vec<Vec<[f64; 2]>> = Vec::new();
+
+        // each cell draws the wall towards the pole and the wall on its
+        // clockwise side, the equator cells also draw the equator
+        let inward = grid.neighbours(pos).into_iter().find(|other| other.row.0 + 1 == pos.row.0);
+        if let Some(inward) = inward {
+            if !cell.is_linked_to(inward) {
+                walls.push(sphere_arc(pos.level, inner, from_turn, to_turn));
+            }
+        }
+
+        let size = grid.ring_size(pos.row);
+        let clockwise = GridPos { col: Col((pos.col.0 + 1) % size), ..pos };
+        if size > 1 && !cell.is_linked_to(clockwise) {
+            walls.push(vec![sphere_point(pos.level, inner, to_turn),
+                            sphere_point(pos.level, outer, to_turn)]);
+        }
+
+        let across = GridPos { level: Level(HEMISPHERES - 1 - pos.level.0), ..pos };
+        if grid.is_equator(pos) && !cell.is_linked_to(across) {
+            walls.push(sphere_arc(pos.level, outer, from_turn, to_turn));
+        }
+
+        walls.iter().for_each(|points| {
+            points.windows(2).for_each(|line| {
+                line_from_to(black, 1.0, line[0], line[1], context.transform, graphics);
+            });
+        });
+    });
+}
+
+fn render_rooms<G, T>(dungeon: &Dungeon, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let floor = [0.93, 0.87, 0.75, 1.0];
+    let door = [0.6, 0.35, 0.1, 1.0];
+
+    dungeon.rooms.iter().for_each(|room| {
+        room.positions().into_iter().for_each(|pos| {
+            let (x1, y1) = cell_origin(pos);
+            rectangle(floor, [x1, y1, DRAW_CELL_SIZE, DRAW_CELL_SIZE], context.transform, graphics);
+        });
+
+        // mark doors with a short bar across the doorway
+        room.doors.iter().for_each(|door_pos| {
+            let (x1, y1) = cell_origin(door_pos.pos);
+            let (x2, y2) = (x1 + DRAW_CELL_SIZE, y1 + DRAW_CELL_SIZE);
+            let third = DRAW_CELL_SIZE / 3.0;
+            let (from, to) = match door_pos.dir {
+                Direction::North => ([x1 + third, y1], [x2 - third, y1]),
+                Direction::South => ([x1 + third, y2], [x2 - third, y2]),
+                Direction::West  => ([x1, y1 + third], [x1, y2 - third]),
+                Direction::East  => ([x2, y1 + third], [x2, y2 - third]),
+            };
+            line_from_to(door, 1.5, from, to, context.transform, graphics);
+        });
+    });
+}
+
+fn render_path<G, T>(maze: &Maze, path: &Option<Vec<GridPos>>, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let red = [0.9, 0.1, 0.1, 1.0];
+    if let Some(path) = path {
+        path.windows(2).for_each(|step| {
+            line_from_to(red, 2.0, maze.cell_centre(step[0]), maze.cell_centre(step[1]),
+                         context.transform, graphics);
+        });
+    }
+}
+
+fn render_weights<G, T>(maze: &Maze, weights: &Option<Weights>, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let weights = match weights {
+        None => return,
+        Some(weights) => weights,
+    };
+    let max_weight = weights.max_weight() as f32;
+
+    maze.grid().positions().into_iter().for_each(|pos| {
+        let weight = weights.get(pos);
+        if weight <= 1 {
+            return;
+        }
+        // more expensive cells are a deeper orange
+        let intensity = weight as f32 / max_weight;
+        let color = [1.0, 0.6 - 0.4 * intensity, 0.1, 0.3 + 0.6 * intensity];
+        maze.cell_polygons(pos).iter().for_each(|points| {
+            polygon(color, points, context.transform, graphics);
+        });
+    });
+}
+
+// Each door is a bar across its passage and each key a dot, in a colour
+// for each pair, with the goal in black
+fn render_puzzle<G, T>(maze: &Maze, puzzle: &Option<Puzzle>, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let puzzle = match puzzle {
+        None => return,
+        Some(puzzle) => puzzle,
+    };
+    let palette = [
+        [0.85, 0.15, 0.15, 1.0], [0.15, 0.35, 0.85, 1.0], [0.1, 0.6, 0.2, 1.0],
+        [0.9, 0.55, 0.05, 1.0], [0.55, 0.2, 0.75, 1.0], [0.05, 0.6, 0.6, 1.0],
+    ];
+    let dot = |color, pos, graphics: &mut G| {
+        let [x, y] = maze.cell_centre(pos);
+        let radius = DRAW_CELL_SIZE * 0.3;
+        ellipse(color, [x - radius, y - radius, radius * 2.0, radius * 2.0], context.transform, graphics);
+    };
+
+    puzzle.locks.iter().enumerate().for_each(|(i, lock)| {
+        let color = palette[i % palette.len()];
+        let ([x1, y1], [x2, y2]) = (maze.cell_centre(lock.door), maze.cell_centre(lock.behind));
+        // halfway between the cells, at right angles to the passage
+        let (x, y) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        let scale = DRAW_CELL_SIZE * 0.45 / length;
+        let (dx, dy) = ((y1 - y2) * scale, (x2 - x1) * scale);
+        line_from_to(color, 2.5, [x - dx, y - dy], [x + dx, y + dy], context.transform, graphics);
+        dot(color, lock.key, graphics);
+    });
+    dot([0.0, 0.0, 0.0, 1.0], puzzle.goal, graphics);
+}
+
+// Colours each cell by the spawn point nearest to it
+fn render_territories<G, T>(maze: &Maze, territories: &Option<Dijkstra>, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+    let territories = match territories {
+        None => return,
+        Some(territories) => territories,
+    };
+    let palette = [
+        [0.95, 0.6, 0.6, 1.0], [0.6, 0.8, 0.95, 1.0], [0.65, 0.9, 0.6, 1.0],
+        [0.95, 0.85, 0.5, 1.0], [0.8, 0.65, 0.95, 1.0], [0.6, 0.9, 0.85, 1.0],
+    ];
+
+    maze.grid().positions().into_iter().for_each(|pos| {
+        let owner = match territories.owner_of(pos) {
+            None => return,
+            Some(owner) => owner,
+        };
+        let index = territories.roots.iter().position(|&root| root == owner).unwrap();
+        let mut color = palette[index % palette.len()];
+        if pos == owner {
+            color = [color[0] * 0.5, color[1] * 0.5, color[2] * 0.5, 1.0];
+        }
+        maze.cell_polygons(pos).iter().for_each(|points| {
+            polygon(color, points, context.transform, graphics);
+        });
+    });
+}
+
+// Plays back a solver's trace, showing the path it found once it's done
+fn render_solution<G, T>(
+    maze: &Maze,
+    solution: &Option<Solution>,
+    start_time: &Option<DateTime<Utc>>,
+    context: &Context,
+    g: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+
+    let (solution, start_time) = match (solution, start_time) {
+        (Some(solution), Some(start_time)) => (solution, *start_time),
+        _ => return,
+    };
+    let count = ((Utc::now() - start_time).num_milliseconds() / SOLVER_SPEED) as usize;
+    let shown = &solution.trace[..count.min(solution.trace.len())];
+
+    match solution.solver {
+        Solver::DeadEndFilling => {
+            let filled_color = [0.6, 0.6, 0.6, 1.0];
+            shown.iter().for_each(|&pos| {
+                maze.cell_polygons(pos).iter().for_each(|points| {
+                    polygon(filled_color, points, context.transform, g);
+                });
+            });
+        },
+        _ => {
+            let walked_color = [0.2, 0.4, 0.9, 1.0];
+            shown.windows(2).for_each(|step| {
+                line_from_to(walked_color, 1.5, maze.cell_centre(step[0]), maze.cell_centre(step[1]),
+                             context.transform, g);
+            });
+        },
+    }
+
+    if count >= solution.trace.len() {
+        render_path(maze, &Some(solution.path.clone()), context, g);
+    }
+}
+
+// Plays back an A* search, shading the cells it has expanded and the open
+// set it's choosing the next one from
+fn render_astar<G, T>(
+    maze: &Maze,
+    astar: &Option<AStar>,
+    start_time: &Option<DateTime<Utc>>,
+    context: &Context,
+    g: &mut G)
+        where G: Graphics<Texture = T>, T: ImageSize {
+
+    let (astar, start_time) = match (astar, start_time) {
+        (Some(astar), Some(start_time)) => (astar, *start_time),
+        _ => return,
+    };
+    let count = ((Utc::now() - start_time).num_milliseconds() / ASTAR_SPEED) as usize;
+    let expanded_color = [0.4, 0.6, 1.0, 1.0];
+    let open_color = [1.0, 0.75, 0.3, 1.0];
+
+    let expanded = astar.expanded.iter()
+        .filter(|&(_, &order)| order < count)
+        .map(|(&pos, _)| (pos, expanded_color));
+    let open = astar.open_after(count).into_iter()
+        .map(|pos| (pos, open_color));
+    expanded.chain(open).for_each(|(pos, color)| {
+        maze.cell_polygons(pos).iter().for_each(|points| {
+            polygon(color, points, context.transform, g);
+        });
+    });
+}
+
 fn background_color_for(dijkstra: &Dijkstra, pos: GridPos) -> Option<types::Color> {
     let max_distance = dijkstra.max_distance as f32;
     let distance = dijkstra.distances.get(&pos);
@@ -85,7 +702,7 @@ fn background_color_for(dijkstra: &Dijkstra, pos: GridPos) -> Option<types::Colo
 }
 
 fn render_dijkstra<G, T>(
-    grid: &ImmutableGrid,
+    maze: &Maze,
     dijkstra: &Option<Dijkstra>,
     start_time: &Option<DateTime<Utc>>,
     context: &Context,
@@ -93,40 +710,148 @@ fn render_dijkstra<G, T>(
         where G: Graphics<Texture = T>, T: ImageSize {
 
     if dijkstra.is_none() { return; }
-    let start_time = start_time.unwrap();
     let dijkstra = dijkstra.as_ref().unwrap();
 
     // depending on how long has passed since the start_time display
     // a different dijkstra state
-    let now = Utc::now();
-    let duration = now - start_time;
-    let count = (duration.num_milliseconds() / DIJKSTRA_SPEED) as usize;
+    let count = match start_time {
+        Some(start_time) => ((Utc::now() - *start_time).num_milliseconds() / DIJKSTRA_SPEED) as usize,
+        None => usize::MAX,
+    };
 
-    grid.iter().for_each(|cell| {
-        let pos = cell.pos;
-        let distance = dijkstra.distances.get(&pos).unwrap();
-        if *distance as usize > count {
-            return;
+    maze.grid().positions().into_iter().for_each(|pos| {
+        match dijkstra.distances.get(&pos) {
+            Some(&distance) if distance as usize <= count => (),
+            _ => return,
         }
 
-        let x1 = pos.col.0 as f64 * DRAW_CELL_SIZE + DRAW_PADDING;
-        // Note: row 0 should be at the bottom
-        let y1 = (ROWS - 1 - pos.row.0) as f64 * DRAW_CELL_SIZE + DRAW_PADDING;
-
         if let Some(color) = background_color_for(dijkstra, pos) {
-            let rectangle = Rectangle::new(color);
-            let dims = [x1, y1, DRAW_CELL_SIZE, DRAW_CELL_SIZE];
-            rectangle.draw(dims, &draw_state::DrawState::default(), context.transform, g);
+            maze.cell_polygons(pos).iter().for_each(|points| {
+                polygon(color, points, context.transform, g);
+            });
         }
     });
 }
 
+// The maze a key asks for, if it's one that makes a new maze
+fn next_maze(key: Key, maze: &Maze, wrap_mode: WrapMode, policy: OpeningPolicy) -> Option<Maze> {
+    match key {
+        Key::S => Some(Maze::Standard(place_openings(ImmutableGrid::new(COLUMNS, ROWS)
+                                                     .with_wrap_mode(wrap_mode)
+                                                     .run_sidewinder_algorithm(), policy))),
+        Key::B => Some(Maze::Standard(place_openings(ImmutableGrid::new(COLUMNS, ROWS)
+                                                     .with_wrap_mode(wrap_mode)
+                                                     .run_binary_tree_algorithm(), policy))),
+        Key::R | Key::C => Some(Maze::Standard(place_openings(ImmutableGrid::new(COLUMNS, ROWS)
+                                                              .with_wrap_mode(wrap_mode)
+                                                              .run_recursive_backtracker_algorithm(), policy))),
+        Key::U => Some(Maze::Upsilon(UpsilonGrid::new(COLUMNS, ROWS)
+                                     .run_recursive_backtracker_algorithm())),
+        Key::W => Some(Maze::Weave(WeaveGrid::new(COLUMNS, ROWS)
+                                   .run_recursive_backtracker_algorithm())),
+        Key::D3 => Some(Maze::ThreeD(Grid3d::new(LEVEL_COLUMNS_3D, ROWS, LEVELS_3D)
+                                     .run_recursive_backtracker_algorithm())),
+        Key::K => Some(Maze::Cube(CubeGrid::new(CUBE_SIZE)
+                                  .run_recursive_backtracker_algorithm())),
+        Key::O => Some(Maze::Sphere(SphereGrid::new(SPHERE_RINGS)
+                                    .run_recursive_backtracker_algorithm())),
+        // the default settings always make a dungeon
+        Key::G => Dungeon::new(COLUMNS, ROWS, DungeonSettings::default()).ok().map(Maze::Dungeon),
+        // braid the current maze, adding loops where there were dead ends
+        Key::Z => match maze {
+            Maze::Standard(grid) => Some(Maze::Standard(grid.clone().braid(BRAID_PROBABILITY))),
+            _ => None,
+        },
+        // cull dead ends from the current maze to make it sparse
+        Key::X => match maze {
+            Maze::Standard(grid) => Some(Maze::Standard(grid.clone().cull_to_density(SPARSE_DENSITY))),
+            _ => None,
+        },
+        // move the current maze's entrance and exit
+        Key::N => match maze {
+            Maze::Standard(grid) => Some(Maze::Standard(place_openings(grid.clone(), policy))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// A maze whose solution from corner to corner is a set length, and the
+// length. The window waits for this, so the attempts and edits are kept to
+// what usually gets there in well under a second.
+fn length_maze(wrap_mode: WrapMode) -> Result<(ImmutableGrid, usize), LengthError> {
+    let generate = || place_openings(ImmutableGrid::new(COLUMNS, ROWS)
+                                     .with_wrap_mode(wrap_mode)
+                                     .run_recursive_backtracker_algorithm(),
+                                     OpeningPolicy::OppositeCorners);
+    let (start, goal) = (GridPos::new(Row(0), Col(0)), GridPos::new(Row(ROWS - 1), Col(COLUMNS - 1)));
+    generate_with_length(start, goal, SOLUTION_LENGTH, LENGTH_ATTEMPTS, LENGTH_EDITS, generate)
+}
+
+// A maze whose solution passes through some random cells in order, and the
+// cells. The openings have to be placed before there are any passages, so
+// they always go in opposite corners.
+fn waypoint_maze(wrap_mode: WrapMode) -> Result<(ImmutableGrid, Vec<GridPos>), WaypointError> {
+    let grid = place_openings(ImmutableGrid::new(COLUMNS, ROWS).with_wrap_mode(wrap_mode),
+                              OpeningPolicy::OppositeCorners);
+    // a torus has no openings, so its ends are the first and last cells
+    // instead
+    let ends: Vec<GridPos> = solution_ends(&grid).into_iter()
+        .flat_map(|(start, goal)| vec![start, goal])
+        .collect();
+    let waypoints: Vec<GridPos> = grid.positions().into_iter()
+        .filter(|pos| !ends.contains(pos))
+        .choose_multiple(&mut rand::thread_rng(), WAYPOINTS);
+    generate_through(grid, &waypoints).map(|grid| (grid, waypoints))
+}
+
+// Dijkstra starts at the entrance, or in the middle of the screen for mazes
+// without one, but not every kind of maze has a cell there. A maze with no
+// cells at all has nowhere to start.
+fn default_root(maze: &Maze) -> Option<GridPos> {
+    if let Some(entrance) = maze.standard_grid().and_then(|grid| grid.entrance()) {
+        return Some(entrance.pos)
+    }
+    let centre = GridPos::new(Row(ROWS/2 - 1), Col(COLUMNS/2 - 1));
+    let positions = maze.grid().positions();
+    if positions.contains(&centre) { Some(centre) } else { positions.first().copied() }
+}
+
+// Where searches and solvers go from and to: the entrance and exit, or the
+// first and last cells for mazes without them. None for a maze with no
+// cells.
+fn endpoints(maze: &Maze) -> Option<(GridPos, GridPos)> {
+    let openings = maze.standard_grid().map(|grid| (grid.entrance(), grid.exit()));
+    if let Some((Some(entrance), Some(exit))) = openings {
+        return Some((entrance.pos, exit.pos))
+    }
+    let positions = maze.grid().positions();
+    Some((*positions.first()?, *positions.last()?))
+}
+
+// Text laid out over several lines, squeezed onto one for the window title
+fn one_line(text: &str) -> String {
+    let lines: Vec<String> = text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .collect();
+    lines.join(", ")
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("bench") => return bench::run(),
+        Some("compare") => return compare::run(&args[1..]),
+        Some("uniformity") => return uniformity::run(),
+        _ => (),
+    }
+
     // Immutable Grid implementation
     // ---------------------------------------
     // 
-    let mut grid = ImmutableGrid::new(COLUMNS, ROWS)
-                   .run_sidewinder_algorithm();
+    let mut maze = Maze::Standard(place_openings(ImmutableGrid::new(COLUMNS, ROWS)
+                                                 .run_sidewinder_algorithm(),
+                                                 OpeningPolicy::OppositeCorners));
 
     // Mutable Linked Cells implementation
     // ---------------------------------------
@@ -135,44 +860,278 @@ fn main() {
     //grid.run_binary_tree_algorithm();
     //grid.run_sidewinder_algorithm();
 
-    let mut dijkstraStartTime: Option<DateTime<Utc>> = None;
-    let mut dijkstra: Option<Dijkstra> = None;
+    let mut wrap_mode = WrapMode::None;
+    let mut heuristic = Heuristic::Zero;
+    let mut wall_follower = Solver::RightWallFollower;
+    let mut opening_policy = OpeningPolicy::OppositeCorners;
+    let mut overlays = Overlays::default();
 
     let canvas_sie =
         [ FULL_DRAW_WIDTH + DRAW_PADDING * 2f64,
           FULL_DRAW_HEIGHT + DRAW_PADDING * 2f64 ];
 
     let mut window: PistonWindow =
-        WindowSettings::new("Mazes for Programmers - Chapter 3!", canvas_sie)
+        WindowSettings::new(TITLE, canvas_sie)
         .exit_on_esc(true).build().unwrap();
 
     while let Some(event) = window.next() {
         window.draw_2d(&event, |context, graphics, _device| {
             clear([1.0; 4], graphics);
-            render_dijkstra(&grid, &dijkstra, &dijkstraStartTime, &context, graphics);
-            render_grid(&grid, &context, graphics);
+            if let Maze::Dungeon(dungeon) = &maze {
+                render_rooms(dungeon, &context, graphics);
+            }
+            render_dijkstra(&maze, &overlays.dijkstra, &overlays.dijkstra_start_time, &context, graphics);
+            render_weights(&maze, &overlays.weights, &context, graphics);
+            render_astar(&maze, &overlays.astar, &overlays.astar_start_time, &context, graphics);
+            render_territories(&maze, &overlays.territories, &context, graphics);
+            match &maze {
+                Maze::Standard(grid) => render_grid(grid, &context, graphics),
+                Maze::Upsilon(grid) => render_upsilon_grid(grid, &context, graphics),
+                Maze::Weave(grid) => render_weave_grid(grid, &context, graphics),
+                Maze::ThreeD(grid) => render_grid_3d(grid, &context, graphics),
+                Maze::Cube(grid) => render_cube_net(grid, &context, graphics),
+                Maze::Sphere(grid) => render_sphere_grid(grid, &context, graphics),
+                Maze::Dungeon(dungeon) => render_grid(&dungeon.grid, &context, graphics),
+            }
+            render_path(&maze, &overlays.path, &context, graphics);
+            render_solution(&maze, &overlays.solution, &overlays.solution_start_time, &context, graphics);
+            render_puzzle(&maze, &overlays.puzzle, &context, graphics);
         });
 
-        if let Some(Button::Keyboard(Key::S)) = event.press_args() {
-            grid = ImmutableGrid::new(COLUMNS, ROWS)
-                   .run_sidewinder_algorithm();
-            dijkstra = None;
-        }
+        if let Some(Button::Keyboard(key)) = event.press_args() {
+            if key == Key::C {
+                // cycle through the wrap modes, then show a maze that uses them
+                wrap_mode = match wrap_mode {
+                    WrapMode::None => WrapMode::Horizontal,
+                    WrapMode::Horizontal => WrapMode::Vertical,
+                    WrapMode::Vertical => WrapMode::Both,
+                    WrapMode::Both => WrapMode::None,
+                };
+            }
 
-        if let Some(Button::Keyboard(Key::B)) = event.press_args() {
-            grid = ImmutableGrid::new(COLUMNS, ROWS)
-                   .run_binary_tree_algorithm();
-            dijkstra = None;
+            if key == Key::N {
+                // cycle through the ways of placing the entrance and exit
+                opening_policy = match opening_policy {
+                    OpeningPolicy::OppositeCorners => OpeningPolicy::FurthestApart,
+                    OpeningPolicy::FurthestApart => OpeningPolicy::Random,
+                    OpeningPolicy::Random => OpeningPolicy::OppositeCorners,
+                };
+            }
+
+            if let Some(next) = next_maze(key, &maze, wrap_mode, opening_policy) {
+                maze = next;
+                overlays = Overlays::default();
+            }
         }
 
         if let Some(Button::Keyboard(Key::D)) = event.press_args() {
-            let d = Dijkstra::new(GridPos::new(Row(ROWS/2 - 1), Col(COLUMNS/2 - 1)))
-                    .run_to_completion(&grid);
-            dijkstra = Some(d);
-            dijkstraStartTime = Some(Utc::now());
+            if let Some(root) = default_root(&maze) {
+                let d = Dijkstra::new(root)
+                        .run_to_completion(maze.grid());
+                overlays.dijkstra = Some(d);
+                overlays.dijkstra_start_time = Some(Utc::now());
+                overlays.dijkstra_log = None;
+            }
+        }
+
+        // step the flood from the root forwards and backwards by hand
+        if let Some(Button::Keyboard(key @ (Key::Right | Key::Left))) = event.press_args() {
+            if let Some(root) = default_root(&maze) {
+                let (log, step, d) = match (overlays.dijkstra_log.take(), overlays.dijkstra.take()) {
+                    (Some((log, step)), Some(d)) => (log, step, d),
+                    _ => {
+                        let log = DijkstraLog::record(&[root], maze.grid());
+                        let d = log.state_at(0);
+                        (log, 0, d)
+                    }
+                };
+                let (step, d) = match key {
+                    Key::Right => log.next_state(&d, step).map(|d| (step + 1, d)),
+                    _ => log.previous_state(&d, step).map(|d| (step - 1, d)),
+                }.unwrap_or((step, d));
+                overlays.status = Some(format!("step {} of {}", step, log.step_count()));
+                overlays.dijkstra = Some(d);
+                overlays.dijkstra_start_time = None;
+                overlays.dijkstra_log = Some((log, step));
+            }
+        }
+
+        // show the longest path through the maze, flooding from one end
+        if let Some(Button::Keyboard(Key::L)) = event.press_args() {
+            if let Some(longest) = longest_path(maze.grid()) {
+                overlays.dijkstra = Some(Dijkstra::new(longest.start).run_to_completion(maze.grid()));
+                overlays.dijkstra_start_time = Some(Utc::now());
+                overlays.dijkstra_log = None;
+                overlays.path = Some(longest.path);
+            }
+        }
+
+        // show the path from the root to the cell furthest from it
+        if let Some(Button::Keyboard(Key::P)) = event.press_args() {
+            if let Some(root) = default_root(&maze) {
+                let d = match overlays.dijkstra.take() {
+                    Some(d) => d,
+                    None => {
+                        overlays.dijkstra_start_time = Some(Utc::now());
+                        Dijkstra::new(root).run_to_completion(maze.grid())
+                    }
+                };
+                let (furthest, _) = d.furthest();
+                overlays.path = Some(d.path_to(furthest, maze.grid()));
+                overlays.dijkstra = Some(d);
+            }
+        }
+
+        // scatter lava over the maze and find the cheapest way from the
+        // entrance to the exit around it
+        if let Some(Button::Keyboard(Key::M)) = event.press_args() {
+            if let Some((start, goal)) = endpoints(&maze) {
+                let mut rng = rand::thread_rng();
+                let weights = maze.grid().positions().iter()
+                    .filter(|&&pos| pos != start && pos != goal && rng.gen_bool(LAVA_PROBABILITY))
+                    .fold(Weights::new(), |weights, &pos| weights.with_weight(pos, LAVA_WEIGHT));
+                let d = WeightedDijkstra::new(start).run_to_completion(maze.grid(), &weights);
+                overlays = Overlays::default();
+                overlays.path = Some(d.path_to(goal, maze.grid(), &weights));
+                overlays.weights = Some(weights);
+            }
+        }
+
+        // search from the entrance to the exit with A*, moving on to the next
+        // heuristic each press so their expansions can be compared. Mazes
+        // that aren't flat only get the zero heuristic, as the others could
+        // miss the shortest path.
+        if let Some(Button::Keyboard(Key::A)) = event.press_args() {
+            if let Some((start, goal)) = endpoints(&maze) {
+                heuristic = match heuristic {
+                    Heuristic::Manhattan => Heuristic::Euclidean,
+                    Heuristic::Euclidean => Heuristic::Zero,
+                    Heuristic::Zero => Heuristic::Manhattan,
+                };
+                let heuristic = if maze.is_flat() { heuristic } else { Heuristic::Zero };
+                let search = AStar::new(start, goal, heuristic).run_to_completion(maze.grid());
+                let flooded = Dijkstra::new(start).run_to_completion(maze.grid()).distances.size();
+                overlays = Overlays::default();
+                overlays.status = Some(format!("A* ({:?}) expanded {} cells, Dijkstra reached {}",
+                                               heuristic, search.expanded.size(), flooded));
+                overlays.path = Some(search.path(maze.grid()));
+                overlays.astar = Some(search);
+                overlays.astar_start_time = Some(Utc::now());
+            }
+        }
+
+        // split the maze into territories around random spawn points
+        // (a maze with no cells has nowhere to put them)
+        let spawns: Vec<GridPos> = match event.press_args() {
+            Some(Button::Keyboard(Key::V)) => maze.grid().positions().into_iter()
+                .choose_multiple(&mut rand::thread_rng(), SPAWN_POINTS),
+            _ => Vec::new(),
+        };
+        if !spawns.is_empty() {
+            let territories = Dijkstra::from_roots(&spawns).run_to_completion(maze.grid());
+            let sizes: Vec<String> = spawns.iter()
+                .map(|&spawn| territories.territory_size(spawn).to_string())
+                .collect();
+            overlays = Overlays::default();
+            overlays.status = Some(format!("territories of {} cells", sizes.join(", ")));
+            overlays.territories = Some(territories);
+        }
+
+        // join the entrance and exit with a search from both ends
+        if let Some(Button::Keyboard(Key::J)) = event.press_args() {
+            if let Some((start, goal)) = endpoints(&maze) {
+                overlays = Overlays::default();
+                overlays.path = Some(bidirectional_path(start, goal, maze.grid()));
+            }
+        }
+
+        // solve the maze from the entrance to the exit the way someone inside it
+        // would, F switching between the left and right hand each press. Press
+        // D first to watch Dijkstra's flood at the same time.
+        if let Some(Button::Keyboard(key)) = event.press_args() {
+            let solver = match key {
+                Key::F => {
+                    wall_follower = match wall_follower {
+                        Solver::LeftWallFollower => Solver::RightWallFollower,
+                        _ => Solver::LeftWallFollower,
+                    };
+                    Some(wall_follower)
+                },
+                Key::T => Some(Solver::Tremaux),
+                Key::E => Some(Solver::DeadEndFilling),
+                _ => None,
+            };
+            if let (Some(solver), Some(grid), Some((start, goal))) = (solver, maze.standard_grid(), endpoints(&maze)) {
+                let solution = solver.solve(grid, start, goal);
+                // keep any Dijkstra flood underneath to compare against,
+                // restarting it alongside the solver if it was playing
+                let now = Utc::now();
+                overlays = Overlays {
+                    dijkstra: overlays.dijkstra.take(),
+                    dijkstra_start_time: overlays.dijkstra_start_time.map(|_| now),
+                    dijkstra_log: overlays.dijkstra_log.take(),
+                    ..Overlays::default()
+                };
+                overlays.status = Some(format!("{:?} took {} steps{}", solver, solution.trace.len(),
+                                               if solution.path.is_empty() { " and gave up" } else { "" }));
+                overlays.solution = Some(solution);
+                overlays.solution_start_time = Some(now);
+            }
+        }
+
+        if let Some(Button::Keyboard(Key::H)) = event.press_args() {
+            match length_maze(wrap_mode) {
+                Ok((grid, length)) => {
+                    maze = Maze::Standard(grid);
+                    overlays = Overlays::default();
+                    overlays.status = Some(format!("solution is {} long", length));
+                },
+                Err(error) => overlays.status = Some(error.to_string()),
+            }
+        }
+
+        if let Some(Button::Keyboard(Key::Y)) = event.press_args() {
+            match waypoint_maze(wrap_mode) {
+                Ok((grid, waypoints)) => {
+                    maze = Maze::Standard(grid);
+                    overlays = Overlays::default();
+                    overlays.status = Some(format!("solution passes through {:?}", waypoints));
+                },
+                Err(error) => overlays.status = Some(error.to_string()),
+            }
+        }
+
+        // lock doors on the way from the entrance to the furthest cell and
+        // hide their keys
+        if let Some(Button::Keyboard(Key::Q)) = event.press_args() {
+            if let Some(root) = default_root(&maze) {
+                let puzzle = place_locks(maze.grid(), root, LOCKS);
+                overlays = Overlays::default();
+                overlays.status = Some(format!("{} locked doors on the way to the black dot", puzzle.locks.len()));
+                overlays.puzzle = Some(puzzle);
+            }
+        }
+
+        // show how hard the current maze is and the numbers describing it
+        if let Some(Button::Keyboard(Key::I)) = event.press_args() {
+            if let Some((start, goal)) = endpoints(&maze) {
+                let stats = one_line(&analysis::analyse(maze.grid(), start, goal).to_string());
+                overlays.status = match difficulty::difficulty(maze.grid(), start, goal) {
+                    Some(difficulty) => Some(format!("{}; {}", difficulty, stats)),
+                    None => Some(stats),
+                };
+            }
+        }
+
+        let title = match &overlays.status {
+            Some(status) => format!("{} - {}", TITLE, status),
+            None => TITLE.to_string(),
+        };
+        if window.get_title() != title {
+            window.set_title(title);
         }
 
-        if let Some(args) = event.update_args() {
+        if let Some(_args) = event.update_args() {
             // render djistra // args.dt
         }
     }
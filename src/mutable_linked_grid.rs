@@ -20,7 +20,7 @@ pub struct CellLink {
 impl CellLink {
     fn new(cell: Weak<RefCell<GridCell>>) -> Self {
         CellLink {
-            cell: cell,
+            cell,
             status: LinkType::Closed,
         }
     }
@@ -76,8 +76,7 @@ impl GridCell {
     fn get_relative_cell(&self, dir: Direction) -> Option<Rc<RefCell<GridCell>>> {
         self.get_link(dir)
             .as_ref()
-            .map(|link| link.cell.upgrade())
-            .flatten()
+            .and_then(|link| link.cell.upgrade())
     }
 
     pub fn set_link_status(&mut self, dir : Direction, status: LinkType) {
@@ -109,13 +108,13 @@ impl MutableLinkedGrid {
             (0..row_count).flat_map(|x| {
                 (0..column_count).map(move |y| {
                     Rc::new(RefCell::new(
-                        GridCell::new(GridPos { row: Row(x), col: Col(y) })))
+                        GridCell::new(GridPos::new(Row(x), Col(y)))))
                 })
             }).collect();
         
         let grid = Self {
-            column_count: column_count,
-            row_count: row_count,
+            column_count,
+            row_count,
             cells: empty_cells
         };
 
@@ -131,19 +130,19 @@ impl MutableLinkedGrid {
                 let north_pos = self.get_relative_cell_pos(c.pos, Direction::North);
                 // use downgrade to get a weak refence
                 c.north = north_pos.map(|pos|
-                    CellLink::new(Rc::downgrade(&self.get_cell(pos))));
+                    CellLink::new(Rc::downgrade(self.get_cell(pos))));
 
                 let east_pos = self.get_relative_cell_pos(c.pos, Direction::East);
                 c.east = east_pos.map(|pos|
-                    CellLink::new(Rc::downgrade(&self.get_cell(pos))));
+                    CellLink::new(Rc::downgrade(self.get_cell(pos))));
 
                 let south_pos = self.get_relative_cell_pos(c.pos, Direction::South);
                 c.south = south_pos.map(|pos|
-                    CellLink::new(Rc::downgrade(&self.get_cell(pos))));
+                    CellLink::new(Rc::downgrade(self.get_cell(pos))));
 
                 let west_pos = self.get_relative_cell_pos(c.pos, Direction::West);
                 c.west = west_pos.map(|pos|
-                    CellLink::new(Rc::downgrade(&self.get_cell(pos))));
+                    CellLink::new(Rc::downgrade(self.get_cell(pos))));
             });
         
         self
@@ -182,20 +181,20 @@ impl MutableLinkedGrid {
         let pos =
             match dir {
                 Direction::North => GridPos {
-                    col: pos.col,
-                    row: Row(pos.row.0 + 1)
+                    row: Row(pos.row.0 + 1),
+                    ..pos
                 },
                 Direction::East  => GridPos {
                     col: Col(pos.col.0 + 1),
-                    row: pos.row
+                    ..pos
                 },
                 Direction::South => GridPos {
-                    col: pos.col,
-                    row: Row(pos.row.0 - 1)
+                    row: Row(pos.row.0 - 1),
+                    ..pos
                 },
                 Direction::West  => GridPos {
                     col: Col(pos.col.0 - 1),
-                    row: pos.row
+                    ..pos
                 },
             };
     
@@ -250,7 +249,7 @@ pub struct Iter<'a> {
 }
 
 impl MutableLinkedGrid {
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter {
             cell_iter: Box::new(self.cells.iter())
         }
@@ -261,9 +260,6 @@ impl<'a> Iterator for Iter<'a> {
     type Item = Ref<'a, GridCell>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.cell_iter.next() {
-            None => None,
-            Some(cell_ref) => Some(cell_ref.borrow()),
-        }
+        self.cell_iter.next().map(|cell_ref| cell_ref.borrow())
     }
 }
\ No newline at end of file
@@ -0,0 +1,123 @@
+use rand::prelude::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::dijkstra::Dijkstra;
+
+// Choosing where a maze's entrance and exit go. The grid only knows how to
+// open its outer wall, the choice can depend on the maze's passages so it's
+// made here where Dijkstra can be used to measure them.
+
+// How to choose where the entrance and exit go
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OpeningPolicy {
+    // the south west and north east corners
+    OppositeCorners,
+    // the two border cells furthest apart through the maze
+    FurthestApart,
+    // any two border cells
+    Random,
+}
+
+// Opens an entrance and an exit in the grid's outer wall, closing any there
+// were before. Meant for a maze that's already been carved, as where they
+// go can depend on its passages. A torus has no outer wall so gets neither.
+pub fn place_openings(grid: ImmutableGrid, policy: OpeningPolicy) -> ImmutableGrid {
+    place_openings_with_rng(grid, policy, &mut rand::thread_rng())
+}
+
+pub fn place_openings_with_rng<R: Rng>(grid: ImmutableGrid, policy: OpeningPolicy, rng: &mut R) -> ImmutableGrid {
+    let grid = grid.without_openings();
+    let border = grid.border_cells();
+    if border.is_empty() {
+        return grid
+    }
+
+    let (entrance, exit) = match policy {
+        OpeningPolicy::OppositeCorners => {
+            // the corners nearest the south west and north east, in case
+            // they've been culled
+            let corner_distance = |pos: &GridPos, row: usize, col: usize| {
+                (pos.row.0 as i64 - row as i64).abs() + (pos.col.0 as i64 - col as i64).abs()
+            };
+            let (last_row, last_col) = (grid.row_count() - 1, grid.column_count() - 1);
+            (*border.iter().min_by_key(|pos| corner_distance(pos, 0, 0)).unwrap(),
+             *border.iter().min_by_key(|pos| corner_distance(pos, last_row, last_col)).unwrap())
+        },
+        OpeningPolicy::FurthestApart => {
+            // like finding the longest path, but only ending on the border
+            let furthest_on_border = |from: GridPos| {
+                let distances = Dijkstra::new(from).run_to_completion(&grid).distances;
+                *border.iter()
+                    .max_by_key(|pos| (distances.get(pos).copied(), **pos))
+                    .unwrap()
+            };
+            let entrance = furthest_on_border(border[0]);
+            (entrance, furthest_on_border(entrance))
+        },
+        OpeningPolicy::Random => {
+            let chosen: Vec<GridPos> = border.iter().copied().choose_multiple(rng, 2);
+            (chosen[0], *chosen.last().unwrap())
+        },
+    };
+    // both were picked from the border, so both have an outer wall to open
+    grid.with_openings(entrance, exit).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::dijkstra::*;
+    use super::{place_openings, OpeningPolicy};
+
+    #[test]
+    fn opposite_corners_open_the_outer_walls() {
+        let grid = place_openings(ImmutableGrid::new(5, 4).run_binary_tree_algorithm(), OpeningPolicy::OppositeCorners);
+        let (entrance, exit) = (GridPos::new(Row(0), Col(0)), GridPos::new(Row(3), Col(4)));
+        assert_eq!(grid.entrance(), Some(Opening { pos: entrance, dir: Direction::West }));
+        assert_eq!(grid.exit(), Some(Opening { pos: exit, dir: Direction::East }));
+        assert!(grid.get(&entrance).unwrap().is_open_to(Direction::West));
+        assert!(grid.get(&exit).unwrap().is_open_to(Direction::East));
+    }
+
+    #[test]
+    fn placing_openings_again_closes_the_old_ones() {
+        let grid = place_openings(ImmutableGrid::new(5, 4).run_binary_tree_algorithm(), OpeningPolicy::OppositeCorners);
+        let grid = place_openings(grid, OpeningPolicy::FurthestApart);
+        let open_to_outside: usize = grid.positions().into_iter()
+            .map(|p| grid.border_sides(p).into_iter().filter(|&dir| grid.get(&p).unwrap().is_open_to(dir)).count())
+            .sum();
+        assert_eq!(open_to_outside, 2);
+    }
+
+    #[test]
+    fn furthest_apart_openings_are_as_far_apart_as_border_cells_get() {
+        let grid = place_openings(ImmutableGrid::new(7, 6).run_recursive_backtracker_algorithm(),
+                                  OpeningPolicy::FurthestApart);
+        let (entrance, exit) = (grid.entrance().unwrap().pos, grid.exit().unwrap().pos);
+        let d = Dijkstra::new(entrance).run_to_completion(&grid);
+        assert!(grid.border_cells().iter().all(|p| d.distances[p] <= d.distances[&exit]));
+    }
+
+    #[test]
+    fn only_border_cells_can_be_opened() {
+        let grid = ImmutableGrid::new(5, 4).run_binary_tree_algorithm();
+        let (corner, middle) = (GridPos::new(Row(0), Col(0)), GridPos::new(Row(2), Col(2)));
+        assert!(grid.clone().with_openings(corner, middle).is_none());
+        assert!(grid.clone().with_openings(middle, corner).is_none());
+        assert!(grid.with_openings(corner, corner).is_some());
+
+        let torus = ImmutableGrid::new(4, 4).with_wrap_mode(WrapMode::Both);
+        assert!(torus.with_openings(corner, corner).is_none());
+    }
+
+    #[test]
+    fn torus_has_no_openings() {
+        let grid = ImmutableGrid::new(4, 4).with_wrap_mode(WrapMode::Both).run_recursive_backtracker_algorithm();
+        let grid = place_openings(grid, OpeningPolicy::Random);
+        assert_eq!(grid.entrance(), None);
+        assert_eq!(grid.exit(), None);
+    }
+}
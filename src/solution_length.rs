@@ -0,0 +1,191 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use rand::prelude::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::dijkstra::Distances;
+use super::flat_dijkstra::FlatDijkstra;
+
+// Generates a perfect maze whose solution between two cells is within a
+// range of lengths. Each maze generated is edited a bounded number of times
+// to bring its solution closer, and if it doesn't get there another maze is
+// generated. An edit cuts a passage on the solution, which splits the maze
+// in two, and joins the halves again through a wall somewhere else, so the
+// maze stays perfect and the solution has to take another way. Every edit
+// floods the maze twice, so FlatDijkstra is used to keep them quick.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LengthError {
+    NotOnGrid { pos: GridPos },
+    // the length that came closest, or None if the goal could never be
+    // reached from the start
+    OutOfRange { closest: Option<usize> },
+}
+
+impl fmt::Display for LengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LengthError::NotOnGrid { pos } =>
+                write!(f, "{:?} isn't on the grid", pos),
+            LengthError::OutOfRange { closest: Some(closest) } =>
+                write!(f, "couldn't get the solution into range, the closest was {} long", closest),
+            LengthError::OutOfRange { closest: None } =>
+                write!(f, "couldn't get the solution into range, the goal was never reachable"),
+        }
+    }
+}
+
+// Generates up to `attempts` mazes and edits each up to `edits` times,
+// returning the first to get into the range with its solution length
+pub fn generate_with_length<F>(start: GridPos, goal: GridPos, range: RangeInclusive<usize>,
+                               attempts: usize, edits: usize, generate: F)
+        -> Result<(ImmutableGrid, usize), LengthError>
+        where F: FnMut() -> ImmutableGrid {
+    generate_with_length_with_rng(start, goal, range, attempts, edits, generate, &mut rand::thread_rng())
+}
+
+pub fn generate_with_length_with_rng<F, R>(start: GridPos, goal: GridPos, range: RangeInclusive<usize>,
+                                           attempts: usize, edits: usize, mut generate: F, rng: &mut R)
+        -> Result<(ImmutableGrid, usize), LengthError>
+        where F: FnMut() -> ImmutableGrid, R: Rng {
+    // how far a solution's length is outside the range
+    let miss = |path: &[GridPos]| match path.len().checked_sub(1) {
+        None => usize::MAX,
+        Some(length) if length < *range.start() => range.start() - length,
+        Some(length) => length.saturating_sub(*range.end()),
+    };
+
+    let mut closest: Option<Vec<GridPos>> = None;
+    for _ in 0..attempts {
+        let mut grid = generate();
+        if let Some(&pos) = [start, goal].iter().find(|&&pos| !grid.contains(pos)) {
+            return Err(LengthError::NotOnGrid { pos })
+        }
+        let mut path = solution(&grid, start, goal);
+        // edits are kept as long as they don't take the solution further
+        // from the range, so it can wander through lengths that miss by
+        // the same amount
+        for _ in 0..edits {
+            if miss(&path) == 0 {
+                break
+            }
+            if let Some(edited) = reroute(&grid, start, &path, rng) {
+                let edited_path = solution(&edited, start, goal);
+                if miss(&edited_path) <= miss(&path) {
+                    grid = edited;
+                    path = edited_path;
+                }
+            }
+        }
+        if miss(&path) == 0 {
+            return Ok((grid, path.len() - 1))
+        }
+        if closest.as_ref().map_or(true, |closest| miss(&path) < miss(closest)) {
+            closest = Some(path);
+        }
+    }
+    Err(LengthError::OutOfRange {
+        closest: closest.and_then(|path| path.len().checked_sub(1)),
+    })
+}
+
+// Empty if the goal can't be reached
+fn solution(grid: &ImmutableGrid, start: GridPos, goal: GridPos) -> Vec<GridPos> {
+    FlatDijkstra::run(start, grid).path_to(goal, grid)
+}
+
+// Cuts a random passage on the solution and joins the side with the start
+// to the side with the goal through a random wall between them, other than
+// the one just made. None if there's nowhere else to join them.
+fn reroute<R: Rng>(grid: &ImmutableGrid, start: GridPos, path: &[GridPos], rng: &mut R) -> Option<ImmutableGrid> {
+    let (from, to) = path.windows(2).map(|pair| (pair[0], pair[1])).choose(rng)?;
+    // on a narrow wrapped grid two walls can lead to the same cell, so take
+    // the one that's open
+    let cell = grid.get(&from)?;
+    let (cut_dir, _) = grid.neighbours(from).into_iter()
+        .find(|&(dir, other)| other == to && cell.is_open_to(dir))?;
+    let cut = grid.clone().unlink_cells(from, cut_dir);
+
+    let start_side = FlatDijkstra::run(start, &cut);
+    let on_start_side = |pos: GridPos| start_side.distance(pos).is_some();
+    let walls: Vec<(GridPos, Direction)> = cut.positions().into_iter()
+        .filter(|&pos| on_start_side(pos))
+        .flat_map(|pos| cut.neighbours(pos).into_iter().map(move |(dir, other)| (pos, dir, other)))
+        .filter(|&(pos, dir, other)| !on_start_side(other) && (pos, dir) != (from, cut_dir))
+        .map(|(pos, dir, _)| (pos, dir))
+        .collect();
+    let &(pos, dir) = walls.iter().choose(rng)?;
+    Some(cut.link_cells(pos, dir))
+}
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::validation::validate_perfect;
+    use super::super::test_support::*;
+    use super::{generate_with_length_with_rng, reroute, LengthError};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn edits_bring_the_solution_into_range() {
+        // sidewinder solutions from corner to corner are short, so this
+        // needs edits to get there rather than another maze
+        seeded(5).for_each(|(seed, mut mazes)| {
+            let mut rng = StdRng::from_rng(&mut mazes).unwrap();
+            let (grid, length) = generate_with_length_with_rng(
+                    pos(0, 0), pos(9, 9), 40..=45, 1, 500,
+                    || ImmutableGrid::new(10, 10).run_sidewinder_algorithm_with_rng(&mut mazes), &mut rng)
+                .unwrap();
+            assert!((40..=45).contains(&length), "seed {}: {}", seed, length);
+            assert_eq!(validate_perfect(&grid), Ok(()), "seed {}", seed);
+        });
+    }
+
+    #[test]
+    fn reports_when_it_cant_get_there() {
+        seeded(5).for_each(|(seed, mut mazes)| {
+            let mut rng = StdRng::from_rng(&mut mazes).unwrap();
+            // a 3x3 maze's solution between opposite corners is at least 4
+            // and can't be longer than 8
+            let error = generate_with_length_with_rng(
+                    pos(0, 0), pos(2, 2), 10..=12, 3, 20,
+                    || ImmutableGrid::new(3, 3).run_binary_tree_algorithm_with_rng(&mut mazes), &mut rng)
+                .unwrap_err();
+            match error {
+                LengthError::OutOfRange { closest: Some(closest) } =>
+                    assert!((4..=8).contains(&closest), "seed {}: {}", seed, closest),
+                error => panic!("seed {}: {:?}", seed, error),
+            }
+        });
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let error = generate_with_length_with_rng(pos(0, 0), pos(3, 3), 0..=10, 1, 1, || ImmutableGrid::new(3, 3), &mut rng)
+            .unwrap_err();
+        assert_eq!(error, LengthError::NotOnGrid { pos: pos(3, 3) });
+        let error = generate_with_length_with_rng(pos(0, 0), pos(2, 2), 0..=10, 1, 1, || ImmutableGrid::new(3, 3), &mut rng)
+            .unwrap_err();
+        assert_eq!(error, LengthError::OutOfRange { closest: None });
+    }
+
+    #[test]
+    fn reroutes_across_a_narrow_wrapped_grid() {
+        // both walls of the first cell lead to the second, and the passage
+        // goes through the west one
+        let grid = ImmutableGrid::new(2, 1).with_wrap_mode(WrapMode::Horizontal)
+            .link_cells(pos(0, 0), Direction::West);
+        seeded(5).for_each(|(seed, mut rng)| {
+            let rerouted = reroute(&grid, pos(0, 0), &[pos(0, 0), pos(0, 1)], &mut rng).unwrap();
+            assert!(rerouted.get(&pos(0, 0)).unwrap().is_open_to(Direction::East), "seed {}", seed);
+            assert!(!rerouted.get(&pos(0, 0)).unwrap().is_open_to(Direction::West), "seed {}", seed);
+        });
+    }
+}
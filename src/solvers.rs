@@ -0,0 +1,261 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::Entry;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+
+// Maze solvers that only know what they can see from where they stand, the
+// way a person inside the maze would solve it, unlike Dijkstra which sees
+// the whole maze at once. Each one records a trace of what it did so it
+// can be played back step by step.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Solver {
+    // keep a hand on the wall and walk until the goal turns up. Only works
+    // if the goal is on a wall joined to the start's, so loops can defeat it
+    LeftWallFollower,
+    RightWallFollower,
+    // mark each passage as it's walked and never walk one more than twice
+    Tremaux,
+    // fill in dead ends until only the way from start to goal is left
+    DeadEndFilling,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub solver: Solver,
+    // the cells the walker stood in, in order, or for dead end filling the
+    // cells filled in, in order
+    pub trace: Vec<GridPos>,
+    // from start to goal, empty if the solver failed
+    pub path: Vec<GridPos>,
+}
+
+impl Solver {
+    pub fn solve(self, grid: &ImmutableGrid, start: GridPos, goal: GridPos) -> Solution {
+        let (trace, path) = match self {
+            Solver::LeftWallFollower => follow_wall(grid, start, goal, Direction::turn_left),
+            Solver::RightWallFollower => follow_wall(grid, start, goal, Direction::turn_right),
+            Solver::Tremaux => tremaux(grid, start, goal),
+            Solver::DeadEndFilling => fill_dead_ends(grid, start, goal),
+        };
+        Solution { solver: self, trace, path }
+    }
+}
+
+// The directions out of the cell there's an open passage in
+fn open_passages(grid: &ImmutableGrid, pos: GridPos) -> Vec<(Direction, GridPos)> {
+    let cell = grid.get(&pos).unwrap();
+    grid.neighbours(pos).into_iter()
+        .filter(|&(dir, _)| cell.is_open_to(dir))
+        .collect()
+}
+
+// Cuts every loop out of a walk, leaving a path with no cell in it twice
+fn erase_loops(trace: &[GridPos]) -> Vec<GridPos> {
+    trace.iter().fold(Vec::new(), |mut path, &pos| {
+        match path.iter().position(|&other| other == pos) {
+            Some(index) => path.truncate(index + 1),
+            None => path.push(pos),
+        }
+        path
+    })
+}
+
+// Walks turning towards the hand on the wall first, then straight on, then
+// the other way, and only turns back at a dead end. Gives up on getting
+// back somewhere it's already been facing the same way, as it's going round
+// in circles from then on.
+fn follow_wall(grid: &ImmutableGrid, start: GridPos, goal: GridPos,
+               turn: fn(&Direction) -> Direction) -> (Vec<GridPos>, Vec<GridPos>) {
+    let mut trace = vec![start];
+    let mut seen: HashSet<(GridPos, Direction)> = HashSet::new();
+    let mut pos = start;
+    let mut heading = Direction::North;
+
+    while pos != goal && seen.insert((pos, heading)) {
+        let passages = open_passages(grid, pos);
+        let towards_wall = turn(&heading);
+        let next = [towards_wall, heading, towards_wall.reverse_dir(), heading.reverse_dir()].iter()
+            .find_map(|&dir| passages.iter().find(|&&(open, _)| open == dir));
+        match next {
+            None => break,
+            Some(&(dir, other)) => {
+                heading = dir;
+                pos = other;
+                trace.push(pos);
+            }
+        }
+    }
+
+    let path = if pos == goal { erase_loops(&trace) } else { Vec::new() };
+    (trace, path)
+}
+
+// Trémaux's algorithm. Passages get a mark each time they're walked. Coming
+// into a cell that's been visited before along a new passage, turn round
+// and go back. Otherwise take a passage that hasn't been walked if there is
+// one, or go back the way we came, but never walk a passage a third time.
+fn tremaux(grid: &ImmutableGrid, start: GridPos, goal: GridPos) -> (Vec<GridPos>, Vec<GridPos>) {
+    let mut trace = vec![start];
+    let mut marks: HashMap<(GridPos, Direction), u8> = HashMap::new();
+    let mut visited: HashSet<GridPos> = HashSet::new();
+    visited.insert(start);
+    let mut pos = start;
+    // the way back along the passage we arrived by
+    let mut back: Option<Direction> = None;
+    let mut revisited = false;
+
+    while pos != goal {
+        let passages = open_passages(grid, pos);
+        let marks_on = |dir: Direction| marks.get(&(pos, dir)).copied().unwrap_or(0);
+        let turn_back = back.filter(|&back| marks_on(back) < 2);
+        let next = match turn_back {
+            Some(back) if revisited && marks_on(back) == 1 => Some(back),
+            _ => passages.iter()
+                .map(|&(dir, _)| dir)
+                .find(|&dir| Some(dir) != back && marks_on(dir) == 0)
+                .or(turn_back)
+                .or_else(|| passages.iter().map(|&(dir, _)| dir).find(|&dir| marks_on(dir) == 1)),
+        };
+        let dir = match next {
+            None => break,
+            Some(dir) => dir,
+        };
+
+        let other = grid.get_relative_cell_pos(pos, dir).unwrap();
+        *marks.entry((pos, dir)).or_insert(0) += 1;
+        *marks.entry((other, dir.reverse_dir())).or_insert(0) += 1;
+        revisited = !visited.insert(other);
+        back = Some(dir.reverse_dir());
+        pos = other;
+        trace.push(pos);
+    }
+
+    let path = if pos == goal { erase_loops(&trace) } else { Vec::new() };
+    (trace, path)
+}
+
+// Fills in every dead end other than the start and goal, and then any cell
+// that filling has turned into a dead end, until none are left. In a perfect
+// maze only the path is left; in one with loops the loops are too, so the
+// path is the shortest way through what's left.
+fn fill_dead_ends(grid: &ImmutableGrid, start: GridPos, goal: GridPos) -> (Vec<GridPos>, Vec<GridPos>) {
+    let mut trace: Vec<GridPos> = Vec::new();
+    let mut filled: HashSet<GridPos> = HashSet::new();
+    let mut to_check: Vec<GridPos> = grid.positions();
+
+    while let Some(pos) = to_check.pop() {
+        if pos == start || pos == goal || filled.contains(&pos) {
+            continue
+        }
+        let open: Vec<GridPos> = open_passages(grid, pos).into_iter()
+            .map(|(_, other)| other)
+            .filter(|other| !filled.contains(other))
+            .collect();
+        if open.len() > 1 {
+            continue
+        }
+        filled.insert(pos);
+        trace.push(pos);
+        to_check.extend(open);
+    }
+
+    // a breadth first search through the cells left over
+    let mut came_from: HashMap<GridPos, GridPos> = HashMap::new();
+    let mut queue: VecDeque<GridPos> = VecDeque::new();
+    queue.push_back(start);
+    came_from.insert(start, start);
+    while let Some(pos) = queue.pop_front() {
+        open_passages(grid, pos).into_iter()
+            .filter(|(_, other)| !filled.contains(other))
+            .for_each(|(_, other)| {
+                if let Entry::Vacant(entry) = came_from.entry(other) {
+                    entry.insert(pos);
+                    queue.push_back(other);
+                }
+            });
+    }
+
+    if !came_from.contains_key(&goal) {
+        return (trace, Vec::new())
+    }
+    let mut path = vec![goal];
+    while path[path.len() - 1] != start {
+        path.push(came_from[&path[path.len() - 1]]);
+    }
+    path.reverse();
+    (trace, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::dijkstra::*;
+    use super::Solver;
+
+    const SOLVERS: [Solver; 4] =
+        [Solver::LeftWallFollower, Solver::RightWallFollower, Solver::Tremaux, Solver::DeadEndFilling];
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn every_solver_finds_the_path_through_a_perfect_maze() {
+        let grid = ImmutableGrid::new(10, 8).run_recursive_backtracker_algorithm();
+        let (start, goal) = (pos(0, 0), pos(7, 9));
+        let expected = Dijkstra::new(start).run_to_completion(&grid).path_to(goal, &grid);
+        SOLVERS.iter().for_each(|&solver| {
+            let solution = solver.solve(&grid, start, goal);
+            assert_eq!(solution.path, expected, "{:?}", solver);
+        });
+    }
+
+    #[test]
+    fn walkers_trace_one_step_at_a_time() {
+        let grid = ImmutableGrid::new(10, 8).run_recursive_backtracker_algorithm().braid(0.5);
+        [Solver::LeftWallFollower, Solver::RightWallFollower, Solver::Tremaux].iter().for_each(|&solver| {
+            let solution = solver.solve(&grid, pos(0, 0), pos(7, 9));
+            assert_eq!(solution.trace[0], pos(0, 0));
+            solution.trace.windows(2).for_each(|step| assert!(grid.links(step[0]).contains(&step[1])));
+        });
+    }
+
+    #[test]
+    fn wall_followers_circle_a_goal_away_from_the_walls() {
+        // with no walls inside the grid the only wall to follow is the
+        // outside one, which never gets near the middle
+        let grid = ImmutableGrid::new(5, 5);
+        let grid = grid.positions().into_iter().fold(grid, |grid, p| {
+            [Direction::North, Direction::East].iter().fold(grid, |grid, &dir| grid.link_cells(p, dir))
+        });
+        let (start, goal) = (pos(0, 0), pos(2, 2));
+        assert!(Solver::LeftWallFollower.solve(&grid, start, goal).path.is_empty());
+        assert!(Solver::RightWallFollower.solve(&grid, start, goal).path.is_empty());
+
+        [Solver::Tremaux, Solver::DeadEndFilling].iter().for_each(|&solver| {
+            let path = solver.solve(&grid, start, goal).path;
+            assert_eq!(path[0], start);
+            assert_eq!(path[path.len() - 1], goal);
+        });
+    }
+
+    #[test]
+    fn no_solver_reaches_a_walled_off_goal() {
+        // a ring round the middle cell, which has no way in
+        let grid = ImmutableGrid::new(3, 3)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::East)
+            .link_cells(pos(0, 2), Direction::North)
+            .link_cells(pos(1, 2), Direction::North)
+            .link_cells(pos(2, 2), Direction::West)
+            .link_cells(pos(2, 1), Direction::West)
+            .link_cells(pos(2, 0), Direction::South)
+            .link_cells(pos(1, 0), Direction::South);
+        SOLVERS.iter().for_each(|&solver| {
+            assert!(solver.solve(&grid, pos(0, 0), pos(1, 1)).path.is_empty(), "{:?}", solver);
+        });
+    }
+}
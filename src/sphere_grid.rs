@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+use im::hashmap::HashMap;
+use im::ordset::OrdSet;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+
+// A maze on a sphere, made from a polar grid on each hemisphere. Level 0 is
+// the northern hemisphere and level 1 the southern. Rows are rings counted
+// out from the pole (row 0 is the single pole cell) and cols go round each
+// ring. Rings are split into more cells as they get longer so the cells stay
+// roughly square, and both hemispheres split the same way so the equator
+// rings line up cell for cell and join the two halves together.
+
+pub const HEMISPHERES: usize = 2;
+
+#[derive(Default, Debug, Clone)]
+pub struct SphereCell {
+    pub pos: GridPos,
+    links: OrdSet<GridPos>,
+}
+
+impl SphereCell {
+    pub fn new(pos: GridPos) -> Self {
+        SphereCell {
+            pos,
+            links: OrdSet::new(),
+        }
+    }
+
+    pub fn is_linked_to(&self, other: GridPos) -> bool {
+        self.links.contains(&other)
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct SphereGrid {
+    // number of cells in each ring, from the pole to the equator
+    ring_sizes: Vec<usize>,
+    cells: HashMap<GridPos, SphereCell>,
+}
+
+impl SphereGrid {
+    pub fn new(ring_count: usize) -> Self {
+        let mut ring_sizes: Vec<usize> = Vec::new();
+        (0..ring_count).for_each(|ring| {
+            let size = match ring {
+                0 => 1,
+                _ => {
+                    // split the cells of the ring inside if they would
+                    // end up much wider than they are tall
+                    let previous = ring_sizes[ring - 1];
+                    let circumference = 2.0 * std::f64::consts::PI * ring as f64;
+                    let estimated_width = circumference / previous as f64;
+                    previous * (estimated_width.round() as usize).max(1)
+                }
+            };
+            ring_sizes.push(size);
+        });
+
+        let grid_cells: HashMap<GridPos, SphereCell> =
+            (0..HEMISPHERES).flat_map(|hemisphere| {
+                ring_sizes.iter().enumerate().flat_map(move |(ring, &size)| {
+                    (0..size).map(move |col| {
+                        let pos = GridPos::on_level(Level(hemisphere), Row(ring), Col(col));
+                        (pos, SphereCell::new(pos))
+                    })
+                }).collect::<Vec<_>>()
+            }).collect();
+
+        SphereGrid {
+            ring_sizes,
+            cells: grid_cells,
+        }
+    }
+
+    pub fn ring_count(&self) -> usize {
+        self.ring_sizes.len()
+    }
+
+    pub fn ring_size(&self, ring: Row) -> usize {
+        self.ring_sizes[ring.0]
+    }
+
+    pub fn is_equator(&self, pos: GridPos) -> bool {
+        pos.row.0 + 1 == self.ring_count()
+    }
+
+    pub fn neighbours(&self, pos: GridPos) -> Vec<GridPos> {
+        let ring = pos.row.0;
+        let size = self.ring_sizes[ring];
+        let mut neighbours: Vec<GridPos> = Vec::new();
+
+        // clockwise and counter clockwise round the ring
+        if size > 1 {
+            neighbours.push(GridPos { col: Col((pos.col.0 + 1) % size), ..pos });
+            neighbours.push(GridPos { col: Col((pos.col.0 + size - 1) % size), ..pos });
+        }
+
+        // in towards the pole
+        if ring > 0 {
+            let ratio = size / self.ring_sizes[ring - 1];
+            neighbours.push(GridPos { row: Row(ring - 1), col: Col(pos.col.0 / ratio), ..pos });
+        }
+
+        // out towards the equator, or across it to the other hemisphere
+        if self.is_equator(pos) {
+            neighbours.push(GridPos { level: Level(HEMISPHERES - 1 - pos.level.0), ..pos });
+        } else {
+            let ratio = self.ring_sizes[ring + 1] / size;
+            (0..ratio).for_each(|i| {
+                neighbours.push(GridPos { row: Row(ring + 1), col: Col(pos.col.0 * ratio + i), ..pos });
+            });
+        }
+
+        neighbours.sort();
+        neighbours.dedup();
+        neighbours
+    }
+
+    pub fn get(&self, pos: &GridPos) -> Option<&SphereCell> {
+        self.cells.get(pos)
+    }
+
+    fn link_single(self, pos: GridPos, other: GridPos) -> Self {
+        let mut cell = self.get(&pos).unwrap().clone();
+        cell.links.insert(other);
+        SphereGrid {
+            ring_sizes: self.ring_sizes,
+            cells: self.cells.update(pos, cell),
+        }
+    }
+
+    pub fn link_cells(self, pos: GridPos, other: GridPos) -> Self {
+        self.link_single(pos, other).link_single(other, pos)
+    }
+
+    pub fn positions(&self) -> Vec<GridPos> {
+        let mut positions: Vec<GridPos> = self.cells.keys().copied().collect();
+        positions.sort();
+        positions
+    }
+
+    pub fn run_recursive_backtracker_algorithm(self) -> Self {
+        let mut rng = rand::thread_rng();
+        let start = match self.positions().into_iter().choose(&mut rng) {
+            None => return self,
+            Some(pos) => pos,
+        };
+
+        let mut grid = self;
+        let mut visited: HashSet<GridPos> = HashSet::new();
+        visited.insert(start);
+        let mut stack: Vec<GridPos> = vec![start];
+        while let Some(&pos) = stack.last() {
+            let next = grid.neighbours(pos).into_iter()
+                .filter(|other| !visited.contains(other))
+                .choose(&mut rng);
+            match next {
+                None => { stack.pop(); },
+                Some(other) => {
+                    grid = grid.link_cells(pos, other);
+                    visited.insert(other);
+                    stack.push(other);
+                }
+            }
+        }
+        grid
+    }
+}
+
+impl MazeGrid for SphereGrid {
+    fn positions(&self) -> Vec<GridPos> {
+        SphereGrid::positions(self)
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        self.get(&pos).unwrap().links.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::dijkstra::*;
+    use super::SphereGrid;
+
+    fn pos(hemisphere: usize, ring: usize, col: usize) -> GridPos {
+        GridPos::on_level(Level(hemisphere), Row(ring), Col(col))
+    }
+
+    #[test]
+    fn rings_get_more_cells_towards_the_equator() {
+        let grid = SphereGrid::new(6);
+        assert_eq!(grid.ring_size(Row(0)), 1);
+        assert!((1..6).all(|ring| grid.ring_size(Row(ring)) >= grid.ring_size(Row(ring - 1))));
+        assert!(grid.ring_size(Row(5)) > grid.ring_size(Row(1)));
+    }
+
+    #[test]
+    fn neighbours_are_symmetric() {
+        let grid = SphereGrid::new(5);
+        grid.positions().into_iter().for_each(|from| {
+            grid.neighbours(from).into_iter().for_each(|to| {
+                assert!(grid.neighbours(to).contains(&from), "{:?} -> {:?}", from, to);
+            });
+        });
+    }
+
+    #[test]
+    fn equator_joins_the_hemispheres() {
+        let grid = SphereGrid::new(4);
+        let equator_cell = pos(0, 3, 2);
+        assert!(grid.is_equator(equator_cell));
+        assert!(grid.neighbours(equator_cell).contains(&pos(1, 3, 2)));
+    }
+
+    #[test]
+    fn recursive_backtracker_reaches_both_poles() {
+        let grid = SphereGrid::new(5).run_recursive_backtracker_algorithm();
+        let d = Dijkstra::new(pos(0, 0, 0)).run_to_completion(&grid);
+        let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
+        distance_positions.sort();
+        assert_eq!(distance_positions, grid.positions());
+        assert!(d.distances.contains_key(&pos(1, 0, 0)));
+    }
+}
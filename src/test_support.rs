@@ -0,0 +1,9 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+// For tests that check something holds across many random mazes. Each run
+// gets a random number generator from a fixed seed, so a failure can be
+// reproduced, and the seed to report if it does.
+pub fn seeded(count: u64) -> impl Iterator<Item = (u64, StdRng)> {
+    (0..count).map(|seed| (seed, StdRng::seed_from_u64(seed)))
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::compare::ALGORITHMS;
+use super::disjoint_set::DisjointSet;
+
+// Checks whether a generator picks every possible maze equally often. On a
+// small grid every spanning tree can be listed, so generate lots of mazes,
+// count how often each tree comes up and run a chi-square test against
+// them all being equally likely. Run with
+//
+//   cargo run --release -- uniformity
+
+const COLUMNS: usize = 3;
+const ROWS: usize = 3;
+const SAMPLES_PER_TREE: usize = 20;
+// the chi-square is converted to a z score with the Wilson-Hilferty
+// approximation, a uniform generator should come out above this less than
+// one time in a thousand
+const Z_THRESHOLD: f64 = 3.09;
+
+// The passages between neighbouring cells that don't wrap, in a fixed order
+fn edges(column_count: usize, row_count: usize) -> Vec<(GridPos, Direction)> {
+    let grid = ImmutableGrid::new(column_count, row_count);
+    grid.positions().into_iter()
+        .flat_map(|pos| [Direction::East, Direction::North].iter().map(move |&dir| (pos, dir)).collect::<Vec<_>>())
+        .filter(|&(pos, dir)| grid.get_relative_cell_pos(pos, dir).is_some())
+        .collect()
+}
+
+// The maze as a bit per edge, set where there's a passage
+pub fn canonical_form(grid: &ImmutableGrid) -> u64 {
+    edges(grid.column_count(), grid.row_count()).iter().enumerate()
+        .filter(|&(_, &(pos, dir))| grid.get(&pos).unwrap().is_open_to(dir))
+        .fold(0, |form, (i, _)| form | 1 << i)
+}
+
+// Every spanning tree of the grid in canonical form, found by trying every
+// set of edges, so only for small grids
+pub fn spanning_trees(column_count: usize, row_count: usize) -> Vec<u64> {
+    let edges = edges(column_count, row_count);
+    let cell_count = column_count * row_count;
+    let index = |pos: GridPos| pos.row.0 * column_count + pos.col.0;
+    assert!(edges.len() < 32, "too many edges to list every spanning tree");
+
+    (0u64..1 << edges.len())
+        .filter(|form| form.count_ones() as usize + 1 == cell_count)
+        .filter(|&form| {
+            // cell_count - 1 edges that never join cells already joined
+            // make a tree
+            let mut joined = DisjointSet::new(cell_count);
+            edges.iter().enumerate()
+                .filter(|&(i, _)| form & 1 << i != 0)
+                .all(|(_, &(pos, dir))| {
+                    let other = match dir {
+                        Direction::East => GridPos { col: Col(pos.col.0 + 1), ..pos },
+                        _ => GridPos { row: Row(pos.row.0 + 1), ..pos },
+                    };
+                    joined.union(index(pos), index(other))
+                })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformityResult {
+    pub tree_count: usize,
+    pub samples: usize,
+    // how many different trees came up, and mazes that weren't trees at all
+    pub trees_seen: usize,
+    pub non_trees: usize,
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    pub z_score: f64,
+}
+
+impl UniformityResult {
+    pub fn looks_uniform(&self) -> bool {
+        self.non_trees == 0 && self.z_score < Z_THRESHOLD
+    }
+}
+
+pub fn test_uniformity<F>(column_count: usize, row_count: usize, samples: usize, mut generate: F)
+        -> UniformityResult
+        where F: FnMut(ImmutableGrid) -> ImmutableGrid {
+    let trees = spanning_trees(column_count, row_count);
+    let mut counts: HashMap<u64, usize> = trees.iter().map(|&tree| (tree, 0)).collect();
+    let mut non_trees = 0;
+    (0..samples).for_each(|_| {
+        let form = canonical_form(&generate(ImmutableGrid::new(column_count, row_count)));
+        match counts.get_mut(&form) {
+            Some(count) => *count += 1,
+            None => non_trees += 1,
+        }
+    });
+
+    let expected = samples as f64 / trees.len() as f64;
+    let chi_square: f64 = counts.values()
+        .map(|&count| (count as f64 - expected).powi(2) / expected)
+        .sum();
+    let degrees_of_freedom = trees.len() - 1;
+    let k = degrees_of_freedom as f64;
+    // with only one possible tree there's nothing to be biased between
+    let z_score = if degrees_of_freedom == 0 {
+        0.0
+    } else {
+        ((chi_square / k).powf(1.0 / 3.0) - (1.0 - 2.0 / (9.0 * k))) / (2.0 / (9.0 * k)).sqrt()
+    };
+
+    UniformityResult {
+        tree_count: trees.len(),
+        samples,
+        trees_seen: counts.values().filter(|&&count| count > 0).count(),
+        non_trees,
+        chi_square,
+        degrees_of_freedom,
+        z_score,
+    }
+}
+
+pub fn run() {
+    let tree_count = spanning_trees(COLUMNS, ROWS).len();
+    let samples = tree_count * SAMPLES_PER_TREE;
+    println!("{} mazes of {}x{} per algorithm, {} possible spanning trees", samples, COLUMNS, ROWS, tree_count);
+    println!("{:<22} {:>11} {:>12} {:>8} {:>9}", "algorithm", "trees seen", "chi-square", "z", "uniform");
+    ALGORITHMS.iter().for_each(|&(name, generate)| {
+        let result = test_uniformity(COLUMNS, ROWS, samples, generate);
+        println!("{:<22} {:>11} {:>12.1} {:>8.1} {:>9}", name, result.trees_seen, result.chi_square,
+                 result.z_score, if result.looks_uniform() { "yes" } else { "no" });
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::immutable_grid::*;
+    use super::super::test_support::*;
+    use super::{spanning_trees, canonical_form, test_uniformity};
+
+    // A uniform generator's z score comes out above this about once in a
+    // billion runs, and a biased one's far above it, so the checks below
+    // hold whatever the seed
+    const Z_BOUND: f64 = 6.0;
+
+    #[test]
+    fn three_by_three_has_192_spanning_trees() {
+        assert_eq!(spanning_trees(3, 3).len(), 192);
+        assert_eq!(spanning_trees(2, 2).len(), 4);
+        assert_eq!(spanning_trees(4, 1).len(), 1);
+    }
+
+    #[test]
+    fn generated_mazes_are_spanning_trees() {
+        let trees = spanning_trees(3, 3);
+        let grid = ImmutableGrid::new(3, 3).run_sidewinder_algorithm();
+        assert!(trees.contains(&canonical_form(&grid)));
+    }
+
+    #[test]
+    fn aldous_broder_is_uniform_and_binary_tree_is_not() {
+        seeded(3).for_each(|(seed, mut rng)| {
+            let aldous_broder = test_uniformity(3, 3, 192 * 20, |grid| grid.run_aldous_broder_algorithm_with_rng(&mut rng));
+            assert_eq!(aldous_broder.non_trees, 0, "seed {}", seed);
+            assert!(aldous_broder.z_score < Z_BOUND, "seed {}: {:?}", seed, aldous_broder);
+
+            let binary_tree = test_uniformity(3, 3, 192 * 20, |grid| grid.run_binary_tree_algorithm_with_rng(&mut rng));
+            assert_eq!(binary_tree.non_trees, 0, "seed {}", seed);
+            assert!(binary_tree.z_score > Z_BOUND, "seed {}: {:?}", seed, binary_tree);
+            // each of the four cells off the top row and east column picks
+            // one of two ways, so it can't make more than 16 trees
+            assert!(binary_tree.trees_seen <= 16, "seed {}", seed);
+        });
+    }
+}
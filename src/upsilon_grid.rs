@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+use im::hashmap::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+
+// An upsilon grid is a checkerboard of octagons and squares. Octagons sit on
+// the cells where row + col is even and connect to all eight surrounding
+// cells, squares fill the gaps and only connect along the compass directions.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UpsilonDirection {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl UpsilonDirection {
+    pub fn reverse_dir(&self) -> UpsilonDirection {
+        match self {
+            UpsilonDirection::North     => UpsilonDirection::South,
+            UpsilonDirection::NorthEast => UpsilonDirection::SouthWest,
+            UpsilonDirection::East      => UpsilonDirection::West,
+            UpsilonDirection::SouthEast => UpsilonDirection::NorthWest,
+            UpsilonDirection::South     => UpsilonDirection::North,
+            UpsilonDirection::SouthWest => UpsilonDirection::NorthEast,
+            UpsilonDirection::West      => UpsilonDirection::East,
+            UpsilonDirection::NorthWest => UpsilonDirection::SouthEast,
+        }
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        matches!(self,
+            UpsilonDirection::NorthEast
+            | UpsilonDirection::SouthEast
+            | UpsilonDirection::SouthWest
+            | UpsilonDirection::NorthWest)
+    }
+
+    // (row, col) offset of the neighbour in this direction. Rows go up.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            UpsilonDirection::North     => ( 1,  0),
+            UpsilonDirection::NorthEast => ( 1,  1),
+            UpsilonDirection::East      => ( 0,  1),
+            UpsilonDirection::SouthEast => (-1,  1),
+            UpsilonDirection::South     => (-1,  0),
+            UpsilonDirection::SouthWest => (-1, -1),
+            UpsilonDirection::West      => ( 0, -1),
+            UpsilonDirection::NorthWest => ( 1, -1),
+        }
+    }
+
+    pub fn iter() -> impl Iterator<Item = UpsilonDirection> {
+        [UpsilonDirection::North, UpsilonDirection::NorthEast,
+         UpsilonDirection::East, UpsilonDirection::SouthEast,
+         UpsilonDirection::South, UpsilonDirection::SouthWest,
+         UpsilonDirection::West, UpsilonDirection::NorthWest].iter().copied()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UpsilonShape {
+    Octagon,
+    Square,
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+pub struct UpsilonCell {
+    pub pos: GridPos,
+    open: [bool; 8],
+}
+
+impl UpsilonCell {
+    pub fn new(pos: GridPos) -> Self {
+        UpsilonCell {
+            pos,
+            open: [false; 8],
+        }
+    }
+
+    pub fn is_open_to(&self, dir: UpsilonDirection) -> bool {
+        self.open[dir as usize]
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct UpsilonGrid {
+    column_count: usize,
+    row_count: usize,
+    cells: HashMap<GridPos, UpsilonCell>,
+}
+
+impl UpsilonGrid {
+    pub fn new(column_count: usize, row_count: usize) -> Self {
+        let grid_cells: HashMap<GridPos, UpsilonCell> =
+            (0..column_count).flat_map(|col| {
+                (0..row_count).map(move |row| {
+                    let pos = GridPos::new(Row(row), Col(col));
+                    (pos, UpsilonCell::new(pos))
+                })
+            }).collect();
+
+        UpsilonGrid {
+            column_count,
+            row_count,
+            cells: grid_cells,
+        }
+    }
+
+    pub fn shape_of(&self, pos: GridPos) -> UpsilonShape {
+        if (pos.row.0 + pos.col.0) % 2 == 0 {
+            UpsilonShape::Octagon
+        } else {
+            UpsilonShape::Square
+        }
+    }
+
+    // The directions a cell of this shape has sides in, whether or not there
+    // is a neighbouring cell on the other side.
+    pub fn directions_for(&self, pos: GridPos) -> Vec<UpsilonDirection> {
+        match self.shape_of(pos) {
+            UpsilonShape::Octagon => UpsilonDirection::iter().collect(),
+            UpsilonShape::Square =>
+                UpsilonDirection::iter().filter(|dir| !dir.is_diagonal()).collect(),
+        }
+    }
+
+    pub fn get_relative_cell_pos(&self, pos: GridPos, dir: UpsilonDirection) -> Option<GridPos> {
+        if dir.is_diagonal() && self.shape_of(pos) == UpsilonShape::Square {
+            return None
+        }
+
+        let (row_offset, col_offset) = dir.offset();
+        let row = pos.row.0 as isize + row_offset;
+        let col = pos.col.0 as isize + col_offset;
+        if row < 0 || col < 0
+            || row >= self.row_count as isize || col >= self.column_count as isize {
+            return None
+        }
+
+        Some(GridPos { row: Row(row as usize), col: Col(col as usize), ..pos })
+    }
+
+    pub fn neighbours(&self, pos: GridPos) -> Vec<(UpsilonDirection, GridPos)> {
+        self.directions_for(pos).into_iter()
+            .filter_map(|dir| self.get_relative_cell_pos(pos, dir).map(|other| (dir, other)))
+            .collect()
+    }
+
+    fn update_cell(self, pos: GridPos, cell: UpsilonCell) -> Self {
+        UpsilonGrid {
+            column_count: self.column_count,
+            row_count: self.row_count,
+            cells: self.cells.update(pos, cell),
+        }
+    }
+
+    pub fn get(&self, pos: &GridPos) -> Option<&UpsilonCell> {
+        self.cells.get(pos)
+    }
+
+    fn link_single(self, pos: GridPos, dir: UpsilonDirection) -> Self {
+        let mut cell = *self.get(&pos).unwrap();
+        cell.open[dir as usize] = true;
+        self.update_cell(pos, cell)
+    }
+
+    pub fn link_cells(self, pos: GridPos, dir: UpsilonDirection) -> Self {
+        match self.get_relative_cell_pos(pos, dir) {
+            None => self,
+            Some(other_pos) =>
+                self.link_single(pos, dir)
+                    .link_single(other_pos, dir.reverse_dir()),
+        }
+    }
+
+    pub fn positions(&self) -> Vec<GridPos> {
+        let mut positions: Vec<GridPos> = self.iter().map(|cell| cell.pos).collect();
+        positions.sort();
+        positions
+    }
+
+    pub fn run_recursive_backtracker_algorithm(self) -> Self {
+        let mut rng = rand::thread_rng();
+        let start = match self.positions().into_iter().choose(&mut rng) {
+            None => return self,
+            Some(pos) => pos,
+        };
+
+        let mut grid = self;
+        let mut visited: HashSet<GridPos> = HashSet::new();
+        visited.insert(start);
+        let mut stack: Vec<GridPos> = vec![start];
+        while let Some(&pos) = stack.last() {
+            let next = grid.neighbours(pos).into_iter()
+                .filter(|(_, other)| !visited.contains(other))
+                .choose(&mut rng);
+            match next {
+                None => { stack.pop(); },
+                Some((dir, other)) => {
+                    grid = grid.link_cells(pos, dir);
+                    visited.insert(other);
+                    stack.push(other);
+                }
+            }
+        }
+        grid
+    }
+}
+
+impl MazeGrid for UpsilonGrid {
+    fn positions(&self) -> Vec<GridPos> {
+        UpsilonGrid::positions(self)
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        let cell = self.get(&pos).unwrap();
+        self.neighbours(pos).into_iter()
+            .filter(|&(dir, _)| cell.is_open_to(dir))
+            .map(|(_, other)| other)
+            .collect()
+    }
+}
+
+// **************************
+// Iter
+// **************************
+
+pub struct Iter {
+    cell_iter: Box<dyn Iterator<Item = (GridPos, UpsilonCell)>>
+}
+
+impl UpsilonGrid {
+    pub fn iter(&self) -> Iter {
+        Iter {
+            cell_iter: Box::new(self.cells.clone().into_iter())
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = UpsilonCell;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cell_iter.next().map(|(_, cell)| cell)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::{UpsilonDirection, UpsilonGrid, UpsilonShape};
+
+    #[test]
+    fn octagons_have_eight_neighbours_and_squares_four() {
+        let grid = UpsilonGrid::new(5, 5);
+        let octagon = GridPos::new(Row(2), Col(2));
+        let square = GridPos::new(Row(2), Col(1));
+        assert_eq!(grid.shape_of(octagon), UpsilonShape::Octagon);
+        assert_eq!(grid.shape_of(square), UpsilonShape::Square);
+        assert_eq!(grid.neighbours(octagon).len(), 8);
+        assert_eq!(grid.neighbours(square).len(), 4);
+    }
+
+    #[test]
+    fn corner_octagon_only_has_neighbours_inside_the_grid() {
+        let grid = UpsilonGrid::new(3, 3);
+        let corner = GridPos::new(Row(0), Col(0));
+        let mut dirs: Vec<UpsilonDirection> =
+            grid.neighbours(corner).into_iter().map(|(dir, _)| dir).collect();
+        dirs.sort_by_key(|&dir| dir as usize);
+        assert_eq!(dirs, vec![UpsilonDirection::North,
+                              UpsilonDirection::NorthEast,
+                              UpsilonDirection::East]);
+    }
+
+    #[test]
+    fn linking_diagonally_opens_both_cells() {
+        let grid = UpsilonGrid::new(3, 3)
+            .link_cells(GridPos::new(Row(0), Col(0)), UpsilonDirection::NorthEast);
+        let other = grid.get(&GridPos::new(Row(1), Col(1))).unwrap();
+        assert!(other.is_open_to(UpsilonDirection::SouthWest));
+        assert_eq!(grid.links(GridPos::new(Row(0), Col(0))),
+                   vec![GridPos::new(Row(1), Col(1))]);
+    }
+
+    #[test]
+    fn recursive_backtracker_links_every_cell() {
+        let grid = UpsilonGrid::new(6, 7).run_recursive_backtracker_algorithm();
+        let link_count: usize = grid.positions().iter()
+            .map(|&pos| grid.links(pos).len())
+            .sum();
+        // each passage is counted from both ends
+        assert_eq!(link_count / 2, 6 * 7 - 1);
+        assert!(grid.positions().iter().all(|&pos| !grid.links(pos).is_empty()));
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::dijkstra::*;
+
+// Checks that a grid is a perfect maze: every passage is open from both
+// ends and stays on the grid apart from the entrance and exit, and there's
+// exactly one way between any two cells, which for a connected grid means
+// one fewer link than cells.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ValidationError {
+    // open towards a cell that isn't open back
+    OneWayLink { pos: GridPos, dir: Direction },
+    // open towards the edge of the grid or a removed cell
+    LinkOffGrid { pos: GridPos, dir: Direction },
+    WrongLinkCount { expected: usize, found: usize },
+    // a cell that can't be reached from the first cell
+    Unreachable { pos: GridPos },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::OneWayLink { pos, dir } =>
+                write!(f, "{:?} is open {:?} but the cell there isn't open back", pos, dir),
+            ValidationError::LinkOffGrid { pos, dir } =>
+                write!(f, "{:?} is open {:?} off the grid", pos, dir),
+            ValidationError::WrongLinkCount { expected, found } =>
+                write!(f, "expected {} links but found {}", expected, found),
+            ValidationError::Unreachable { pos } =>
+                write!(f, "{:?} can't be reached", pos),
+        }
+    }
+}
+
+pub fn validate_perfect(grid: &ImmutableGrid) -> Result<(), ValidationError> {
+    let positions = grid.positions();
+
+    let mut link_ends = 0;
+    for &pos in &positions {
+        let cell = grid.get(&pos).unwrap();
+        // the entrance and exit are meant to be open to the outside
+        for dir in Direction::iter().filter(|&dir| cell.is_open_to(dir) && !grid.is_opening(pos, dir)) {
+            let other = match grid.get_relative_cell_pos(pos, dir).and_then(|other| grid.get(&other)) {
+                None => return Err(ValidationError::LinkOffGrid { pos, dir }),
+                Some(other) => other,
+            };
+            if !other.is_open_to(dir.reverse_dir()) {
+                return Err(ValidationError::OneWayLink { pos, dir })
+            }
+            link_ends += 1;
+        }
+    }
+
+    let expected = positions.len().saturating_sub(1);
+    if link_ends / 2 != expected {
+        return Err(ValidationError::WrongLinkCount { expected, found: link_ends / 2 })
+    }
+
+    if let Some(&first) = positions.first() {
+        let d = Dijkstra::new(first).run_to_completion(grid);
+        if let Some(&pos) = positions.iter().find(|pos| !d.distances.contains_key(pos)) {
+            return Err(ValidationError::Unreachable { pos })
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::openings::*;
+    use super::{validate_perfect, ValidationError};
+
+    const SIZES: [(usize, usize); 8] = [(0, 0), (1, 1), (1, 7), (7, 1), (2, 2), (5, 3), (3, 5), (12, 9)];
+    const SEEDS: u64 = 25;
+
+    type SeededGenerator = fn(ImmutableGrid, &mut StdRng) -> ImmutableGrid;
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn every_generator_makes_perfect_mazes() {
+        let generators: [(&str, SeededGenerator); 4] = [
+            ("binary tree", |grid, rng| grid.run_binary_tree_algorithm_with_rng(rng)),
+            ("sidewinder", |grid, rng| grid.run_sidewinder_algorithm_with_rng(rng)),
+            ("recursive backtracker", |grid, rng| grid.run_recursive_backtracker_algorithm_with_rng(rng)),
+            ("aldous-broder", |grid, rng| grid.run_aldous_broder_algorithm_with_rng(rng)),
+        ];
+        let wrap_modes = [WrapMode::None, WrapMode::Horizontal, WrapMode::Vertical, WrapMode::Both];
+
+        generators.iter().for_each(|&(name, generate)| {
+            SIZES.iter().for_each(|&(columns, rows)| {
+                wrap_modes.iter().for_each(|&wrap_mode| {
+                    (0..SEEDS).for_each(|seed| {
+                        let grid = ImmutableGrid::new(columns, rows).with_wrap_mode(wrap_mode);
+                        let grid = generate(grid, &mut StdRng::seed_from_u64(seed));
+                        assert_eq!(validate_perfect(&grid), Ok(()),
+                                   "{} {}x{} {:?} seed {}", name, columns, rows, wrap_mode, seed);
+                    });
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn openings_are_allowed_off_the_grid() {
+        let policies = [OpeningPolicy::OppositeCorners, OpeningPolicy::FurthestApart, OpeningPolicy::Random];
+        policies.iter().for_each(|&policy| {
+            let grid = place_openings(ImmutableGrid::new(6, 4).run_sidewinder_algorithm(), policy);
+            assert!(grid.entrance().is_some() && grid.exit().is_some());
+            assert_eq!(validate_perfect(&grid), Ok(()), "{:?}", policy);
+        });
+    }
+
+    #[test]
+    fn same_seed_makes_the_same_maze() {
+        let maze = |seed| ImmutableGrid::new(10, 10)
+            .run_recursive_backtracker_algorithm_with_rng(&mut StdRng::seed_from_u64(seed));
+        let (a, b) = (maze(7), maze(7));
+        assert!(a.positions().into_iter().all(|p| a.links(p) == b.links(p)));
+    }
+
+    #[test]
+    fn finds_what_is_wrong() {
+        let grid = ImmutableGrid::new(2, 2)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 0), Direction::North);
+        assert_eq!(validate_perfect(&grid), Err(ValidationError::WrongLinkCount { expected: 3, found: 2 }));
+
+        let grid = grid.link_single(pos(1, 1), Direction::North);
+        assert_eq!(validate_perfect(&grid), Err(ValidationError::LinkOffGrid { pos: pos(1, 1), dir: Direction::North }));
+
+        let grid = ImmutableGrid::new(2, 2)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 0), Direction::North)
+            .link_single(pos(0, 1), Direction::North);
+        assert_eq!(validate_perfect(&grid), Err(ValidationError::OneWayLink { pos: pos(0, 1), dir: Direction::North }));
+
+        // right number of links, but one makes a loop and leaves a cell out
+        let grid = ImmutableGrid::new(3, 2)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::North)
+            .link_cells(pos(1, 1), Direction::West)
+            .link_cells(pos(1, 0), Direction::South)
+            .link_cells(pos(1, 2), Direction::South);
+        assert_eq!(validate_perfect(&grid), Err(ValidationError::Unreachable { pos: pos(0, 2) }));
+    }
+}
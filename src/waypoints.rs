@@ -0,0 +1,260 @@
+use std::collections::HashSet;
+use std::fmt;
+use rand::prelude::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+use super::dijkstra::*;
+use super::weighted_dijkstra::*;
+
+// Generates a maze whose solution from the entrance to the exit passes
+// through a list of waypoints in order. The solution is carved first, as a
+// spine of paths from each waypoint to the next that keep clear of the
+// spine so far and the waypoints still to come. The rest of the maze then
+// grows onto the spine with Wilson's algorithm, and as the result is a
+// perfect maze the spine is the only way through.
+
+// How many times to try each part of the spine again when it walls in a
+// waypoint still to come, and to start the whole spine again when an
+// earlier part has made a later one impossible
+const ATTEMPTS: usize = 20;
+const RESTARTS: usize = 5;
+// Each part of the spine is the cheapest way when every cell costs a random
+// 1 to WIGGLE to pass through, so it wanders rather than going straight
+const WIGGLE: u32 = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WaypointError {
+    // the grid has to start with no passages, as it's carved from scratch
+    AlreadyCarved,
+    NotOnGrid { pos: GridPos },
+    // the solution would have to pass through the cell twice
+    Repeated { pos: GridPos },
+    // there's no way between the two that keeps clear of the spine and the
+    // other waypoints, or none of the attempts left one for those to come
+    NoRoute { from: GridPos, to: GridPos },
+}
+
+impl fmt::Display for WaypointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WaypointError::AlreadyCarved =>
+                write!(f, "the grid already has passages"),
+            WaypointError::NotOnGrid { pos } =>
+                write!(f, "waypoint {:?} isn't on the grid", pos),
+            WaypointError::Repeated { pos } =>
+                write!(f, "waypoint {:?} comes up more than once", pos),
+            WaypointError::NoRoute { from, to } =>
+                write!(f, "no way from {:?} to {:?} that keeps clear of the rest of the solution", from, to),
+        }
+    }
+}
+
+// Carves a maze into a grid with no passages yet. The grid's entrance and
+// exit are the ends of the solution, or the first and last cells if it
+// doesn't have them. Like Aldous-Broder the cells must all be reachable from
+// each other.
+pub fn generate_through(grid: ImmutableGrid, waypoints: &[GridPos]) -> Result<ImmutableGrid, WaypointError> {
+    generate_through_with_rng(grid, waypoints, &mut rand::thread_rng())
+}
+
+pub fn generate_through_with_rng<R: Rng>(grid: ImmutableGrid, waypoints: &[GridPos], rng: &mut R)
+        -> Result<ImmutableGrid, WaypointError> {
+    let positions = grid.positions();
+    if positions.iter().any(|&pos| !grid.links(pos).is_empty()) {
+        return Err(WaypointError::AlreadyCarved)
+    }
+    let (start, goal) = match solution_ends(&grid) {
+        Some(ends) => ends,
+        None => return Ok(grid),
+    };
+
+    if let Some(&pos) = waypoints.iter().find(|&&pos| !grid.contains(pos)) {
+        return Err(WaypointError::NotOnGrid { pos })
+    }
+    // waypoints on the entrance or exit, or twice in a row, are already
+    // passed through
+    let mut stops: Vec<GridPos> = Some(start).into_iter()
+        .chain(waypoints.iter().copied())
+        .chain(Some(goal))
+        .collect();
+    stops.dedup();
+    let mut seen: HashSet<GridPos> = HashSet::new();
+    if let Some(&pos) = stops.iter().find(|&&pos| !seen.insert(pos)) {
+        return Err(WaypointError::Repeated { pos })
+    }
+
+    let spine = (1..RESTARTS)
+        .fold(carve_spine(&grid, &stops, rng), |spine, _| spine.or_else(|_| carve_spine(&grid, &stops, rng)))?;
+    Ok(grow_onto(spine, &positions, rng))
+}
+
+// Where the solution through the waypoints starts and ends, None if the
+// grid has no cells
+pub fn solution_ends(grid: &ImmutableGrid) -> Option<(GridPos, GridPos)> {
+    match (grid.entrance(), grid.exit()) {
+        (Some(entrance), Some(exit)) => Some((entrance.pos, exit.pos)),
+        _ => {
+            let positions = grid.positions();
+            Some((*positions.first()?, *positions.last()?))
+        },
+    }
+}
+
+// Returns the grid with the spine carved and the cells on it
+fn carve_spine<R: Rng>(grid: &ImmutableGrid, stops: &[GridPos], rng: &mut R)
+        -> Result<(ImmutableGrid, HashSet<GridPos>), WaypointError> {
+    let mut grid = grid.clone();
+    let mut spine: HashSet<GridPos> = HashSet::new();
+    spine.insert(stops[0]);
+    for i in 0..stops.len() - 1 {
+        let (from, to) = (stops[i], stops[i + 1]);
+        let no_route = WaypointError::NoRoute { from, to };
+        if !leaves_a_way(&grid, &spine, &stops[i..]) {
+            return Err(no_route)
+        }
+        let region = Region { grid: &grid, used: &spine, stops: &stops[i..] };
+        let (path, used) = (0..ATTEMPTS)
+            .map(|_| {
+                let weights = region.positions().into_iter()
+                    .fold(Weights::new(), |weights, pos| weights.with_weight(pos, rng.gen_range(1, WIGGLE + 1)));
+                let path = WeightedDijkstra::new(from).run_to_completion(&region, &weights)
+                    .path_to(to, &region, &weights);
+                let mut used = spine.clone();
+                used.extend(path.iter().copied());
+                (path, used)
+            })
+            .find(|(_, used)| leaves_a_way(&grid, used, &stops[i + 1..]))
+            .ok_or(no_route)?;
+        spine = used;
+        grid = path.windows(2).fold(grid, |grid, pair| {
+            let (dir, _) = grid.neighbours(pair[0]).into_iter().find(|&(_, other)| other == pair[1]).unwrap();
+            grid.link_cells(pair[0], dir)
+        });
+    }
+    Ok((grid, spine))
+}
+
+// Whether there's still a way from each stop to the next. The ways aren't
+// checked against each other, which is what the restarts are for.
+fn leaves_a_way(grid: &ImmutableGrid, used: &HashSet<GridPos>, stops: &[GridPos]) -> bool {
+    (0..stops.len().saturating_sub(1)).all(|i| {
+        let region = Region { grid, used, stops: &stops[i..] };
+        Dijkstra::new(stops[i]).run_to_completion(&region).distances.contains_key(&stops[i + 1])
+    })
+}
+
+// The cells a way from the first stop to the second can go through, which
+// are the ones not already used and not one of the stops after the second.
+// Every cell is linked to its neighbours in the region so the searches can
+// cross it.
+struct Region<'a> {
+    grid: &'a ImmutableGrid,
+    used: &'a HashSet<GridPos>,
+    stops: &'a [GridPos],
+}
+
+impl<'a> Region<'a> {
+    fn contains(&self, pos: GridPos) -> bool {
+        self.stops[..2].contains(&pos) || !(self.used.contains(&pos) || self.stops.contains(&pos))
+    }
+}
+
+impl<'a> MazeGrid for Region<'a> {
+    fn positions(&self) -> Vec<GridPos> {
+        self.grid.positions().into_iter().filter(|&pos| self.contains(pos)).collect()
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        self.grid.neighbours(pos).into_iter()
+            .map(|(_, other)| other)
+            .filter(|&other| self.contains(other))
+            .collect()
+    }
+}
+
+// Wilson's algorithm, starting with the spine as the tree instead of a
+// single cell
+fn grow_onto<R: Rng>((grid, mut tree): (ImmutableGrid, HashSet<GridPos>), positions: &[GridPos], rng: &mut R)
+        -> ImmutableGrid {
+    positions.iter().fold(grid, |grid, &start| {
+        if tree.contains(&start) {
+            return grid
+        }
+        let steps = loop_erased_walk(&grid, start, |pos| tree.contains(&pos), rng);
+        tree.extend(steps.iter().map(|&(pos, _)| pos));
+        steps.into_iter().fold(grid, |grid, (pos, dir)| grid.link_cells(pos, dir))
+    })
+}
+
+// A random walk from `from` until it steps onto a cell where `stop` is
+// true, with any loops it made cut out. Each step is a cell and the way
+// out of it.
+fn loop_erased_walk<R, S>(grid: &ImmutableGrid, from: GridPos, stop: S, rng: &mut R) -> Vec<(GridPos, Direction)>
+        where R: Rng, S: Fn(GridPos) -> bool {
+    let mut steps: Vec<(GridPos, Direction)> = Vec::new();
+    let mut on_path: HashSet<GridPos> = HashSet::new();
+    let mut pos = from;
+    while !stop(pos) {
+        let (dir, next) = grid.neighbours(pos).into_iter().choose(rng).unwrap();
+        on_path.insert(pos);
+        steps.push((pos, dir));
+        pos = next;
+        if on_path.contains(&pos) {
+            let loop_start = steps.iter().position(|&(other, _)| other == pos).unwrap();
+            steps.drain(loop_start..).for_each(|(other, _)| { on_path.remove(&other); });
+        }
+    }
+    steps
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::openings::*;
+    use super::super::dijkstra::*;
+    use super::super::validation::validate_perfect;
+    use super::super::test_support::*;
+    use super::{generate_through_with_rng, WaypointError};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    #[test]
+    fn solution_passes_through_the_waypoints_in_order() {
+        let waypoints = [pos(6, 1), pos(1, 6), pos(4, 4)];
+        seeded(10).for_each(|(seed, mut rng)| {
+            let grid = place_openings(ImmutableGrid::new(8, 8), OpeningPolicy::OppositeCorners);
+            let grid = generate_through_with_rng(grid, &waypoints, &mut rng).unwrap();
+            assert_eq!(validate_perfect(&grid), Ok(()), "seed {}", seed);
+
+            let (start, goal) = (grid.entrance().unwrap().pos, grid.exit().unwrap().pos);
+            let path = Dijkstra::new(start).run_to_completion(&grid).path_to(goal, &grid);
+            let indices: Vec<usize> = waypoints.iter()
+                .map(|waypoint| path.iter().position(|pos| pos == waypoint).unwrap())
+                .collect();
+            assert!(indices.windows(2).all(|pair| pair[0] < pair[1]), "seed {}", seed);
+        });
+    }
+
+    #[test]
+    fn reports_waypoints_it_cant_use() {
+        seeded(5).for_each(|(_, mut rng)| {
+            let grid = ImmutableGrid::new(4, 1);
+            assert_eq!(generate_through_with_rng(grid.clone(), &[pos(1, 0)], &mut rng).unwrap_err(),
+                       WaypointError::NotOnGrid { pos: pos(1, 0) });
+            assert_eq!(generate_through_with_rng(grid.clone(), &[pos(0, 1), pos(0, 2), pos(0, 1)], &mut rng).unwrap_err(),
+                       WaypointError::Repeated { pos: pos(0, 1) });
+            // along a corridor the second waypoint is in the way of the first
+            assert_eq!(generate_through_with_rng(grid.clone(), &[pos(0, 2), pos(0, 1)], &mut rng).unwrap_err(),
+                       WaypointError::NoRoute { from: pos(0, 0), to: pos(0, 2) });
+            // the ends themselves can be given as waypoints
+            assert!(generate_through_with_rng(grid.clone(), &[pos(0, 0), pos(0, 3)], &mut rng).is_ok());
+            assert_eq!(generate_through_with_rng(grid.link_cells(pos(0, 0), Direction::East), &[], &mut rng).unwrap_err(),
+                       WaypointError::AlreadyCarved);
+        });
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use im::hashmap::*;
+use rand::seq::IteratorRandom;
+
+use super::grid_primitives::*;
+use super::immutable_grid::*;
+
+// A weave grid lets a passage tunnel under a perpendicular corridor. The
+// visible cells are a normal ImmutableGrid; a tunnel opens the cells either
+// side of the crossing towards each other and records a hidden "under cell"
+// at the crossing, so the over cell keeps its own walls closed.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Orientation {
+    // the passage runs east-west
+    Horizontal,
+    // the passage runs north-south
+    Vertical,
+}
+
+impl Orientation {
+    pub fn of(dir: Direction) -> Orientation {
+        match dir {
+            Direction::North | Direction::South => Orientation::Vertical,
+            Direction::East | Direction::West => Orientation::Horizontal,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct WeaveGrid {
+    grid: ImmutableGrid,
+    under_cells: HashMap<GridPos, Orientation>,
+}
+
+impl WeaveGrid {
+    pub fn new(column_count: usize, row_count: usize) -> Self {
+        WeaveGrid {
+            grid: ImmutableGrid::new(column_count, row_count),
+            under_cells: HashMap::new(),
+        }
+    }
+
+    // The visible (over) cells
+    pub fn over_grid(&self) -> &ImmutableGrid {
+        &self.grid
+    }
+
+    // The orientation of the passage running under this position, if any
+    pub fn under_cell(&self, pos: GridPos) -> Option<Orientation> {
+        self.under_cells.get(&pos).copied()
+    }
+
+    // Where a passage leaving `pos` in `dir` comes out, following it under
+    // any crossings on the way
+    fn passage_end(&self, pos: GridPos, dir: Direction) -> Option<GridPos> {
+        let mut next = self.grid.get_relative_cell_pos(pos, dir)?;
+        while self.under_cell(next) == Some(Orientation::of(dir)) {
+            next = self.grid.get_relative_cell_pos(next, dir)?;
+        }
+        Some(next)
+    }
+
+    // A passage can only go under a cell that is a straight corridor running
+    // across it, and that isn't already crossed
+    fn can_tunnel_under(&self, pos: GridPos, dir: Direction) -> bool {
+        if self.under_cells.contains_key(&pos) {
+            return false
+        }
+        let cell = self.grid.get(&pos).unwrap();
+        match Orientation::of(dir) {
+            Orientation::Horizontal =>
+                cell.north_open && cell.south_open && !cell.east_open && !cell.west_open,
+            Orientation::Vertical =>
+                cell.east_open && cell.west_open && !cell.north_open && !cell.south_open,
+        }
+    }
+
+    pub fn link_cells(self, pos: GridPos, dir: Direction) -> Self {
+        WeaveGrid {
+            grid: self.grid.link_cells(pos, dir),
+            under_cells: self.under_cells,
+        }
+    }
+
+    // Open a passage from `pos` under its neighbour in `dir` to the cell
+    // beyond it
+    pub fn tunnel_under(self, pos: GridPos, dir: Direction) -> Self {
+        let under_pos = self.grid.get_relative_cell_pos(pos, dir).unwrap();
+        let other_pos = self.grid.get_relative_cell_pos(under_pos, dir).unwrap();
+        WeaveGrid {
+            grid: self.grid
+                      .link_single(pos, dir)
+                      .link_single(other_pos, dir.reverse_dir()),
+            under_cells: self.under_cells.update(under_pos, Orientation::of(dir)),
+        }
+    }
+
+    pub fn run_recursive_backtracker_algorithm(self) -> Self {
+        let mut rng = rand::thread_rng();
+        let start = match self.grid.positions().into_iter().choose(&mut rng) {
+            None => return self,
+            Some(pos) => pos,
+        };
+
+        let mut grid = self;
+        let mut visited: HashSet<GridPos> = HashSet::new();
+        visited.insert(start);
+        let mut stack: Vec<GridPos> = vec![start];
+        while let Some(&pos) = stack.last() {
+            // (direction, destination, whether it goes under the neighbour)
+            let next = Direction::iter().filter_map(|dir| {
+                let neighbour = grid.grid.get_relative_cell_pos(pos, dir)?;
+                if !visited.contains(&neighbour) {
+                    return Some((dir, neighbour, false))
+                }
+                let beyond = grid.grid.get_relative_cell_pos(neighbour, dir)?;
+                if !visited.contains(&beyond) && grid.can_tunnel_under(neighbour, dir) {
+                    return Some((dir, beyond, true))
+                }
+                None
+            }).choose(&mut rng);
+
+            match next {
+                None => { stack.pop(); },
+                Some((dir, other, tunnel)) => {
+                    grid = if tunnel {
+                        grid.tunnel_under(pos, dir)
+                    } else {
+                        grid.link_cells(pos, dir)
+                    };
+                    visited.insert(other);
+                    stack.push(other);
+                }
+            }
+        }
+        grid
+    }
+}
+
+// Under cells are not positions of their own: a tunnel links the cells at
+// either end directly, so going under a crossing counts as a single step.
+impl MazeGrid for WeaveGrid {
+    fn positions(&self) -> Vec<GridPos> {
+        self.grid.positions()
+    }
+
+    fn links(&self, pos: GridPos) -> Vec<GridPos> {
+        let cell = self.grid.get(&pos).unwrap();
+        Direction::iter()
+            .filter(|&dir| cell.is_open_to(dir))
+            .filter_map(|dir| self.passage_end(pos, dir))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::dijkstra::*;
+    use super::{Orientation, WeaveGrid};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    // A vertical corridor through the middle of a 3x3 grid, with a tunnel
+    // running under it from west to east
+    fn crossing() -> WeaveGrid {
+        WeaveGrid::new(3, 3)
+            .link_cells(pos(0, 1), Direction::North)
+            .link_cells(pos(1, 1), Direction::North)
+            .tunnel_under(pos(1, 0), Direction::East)
+    }
+
+    #[test]
+    fn tunnel_links_the_cells_either_side_of_the_crossing() {
+        let grid = crossing();
+        assert_eq!(grid.under_cell(pos(1, 1)), Some(Orientation::Horizontal));
+        assert_eq!(grid.links(pos(1, 0)), vec![pos(1, 2)]);
+        assert_eq!(grid.links(pos(1, 2)), vec![pos(1, 0)]);
+    }
+
+    #[test]
+    fn over_cell_keeps_its_own_links() {
+        let grid = crossing();
+        let mut links = grid.links(pos(1, 1));
+        links.sort();
+        assert_eq!(links, vec![pos(0, 1), pos(2, 1)]);
+    }
+
+    #[test]
+    fn dijkstra_reaches_every_cell_of_a_weave_maze() {
+        let grid = WeaveGrid::new(12, 9).run_recursive_backtracker_algorithm();
+        let d = Dijkstra::new(pos(0, 0)).run_to_completion(&grid);
+        let mut distance_positions: Vec<GridPos> = d.distances.keys().copied().collect();
+        distance_positions.sort();
+        assert_eq!(distance_positions, grid.positions());
+    }
+}
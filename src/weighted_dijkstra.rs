@@ -0,0 +1,176 @@
+use rpds::HashTrieMap;
+use rpds::RedBlackTreeSet;
+
+use super::grid_primitives::*;
+
+// Dijkstra for mazes where cells cost different amounts to walk into, like
+// lava or mud. Distances are the total cost of the cheapest route rather
+// than the number of steps, so braided mazes route around expensive cells.
+
+// The cost of entering each cell, 1 unless set otherwise
+#[derive(Debug, Clone, Default)]
+pub struct Weights {
+    weights: HashTrieMap<GridPos, u32>,
+}
+
+impl Weights {
+    pub fn new() -> Self {
+        Weights { weights: HashTrieMap::new() }
+    }
+
+    // Every cell has to cost something to enter. A free cell would have the
+    // same distance as the one before it, and path_to couldn't tell which
+    // way it had come.
+    pub fn with_weight(&self, pos: GridPos, weight: u32) -> Self {
+        assert!(weight >= 1, "cells must cost at least 1 to enter, {:?} was given {}", pos, weight);
+        Weights { weights: self.weights.insert(pos, weight) }
+    }
+
+    pub fn get(&self, pos: GridPos) -> u32 {
+        self.weights.get(&pos).copied().unwrap_or(1)
+    }
+
+    pub fn max_weight(&self) -> u32 {
+        self.weights.values().copied().max().unwrap_or(1).max(1)
+    }
+}
+
+pub struct WeightedDijkstra {
+    pub root: GridPos,
+    pub distances: HashTrieMap<GridPos, u32>,
+    // ordered by distance so the cheapest cell to reach is first
+    pub frontier: RedBlackTreeSet<(u32, GridPos)>,
+    pub max_distance: u32,
+}
+
+impl WeightedDijkstra {
+    pub fn new(root: GridPos) -> Self {
+        Self {
+            root,
+            distances: HashTrieMap::new().insert(root, 0u32),
+            frontier: RedBlackTreeSet::new().insert((0u32, root)),
+            max_distance: 0u32,
+        }
+    }
+
+    pub fn run_to_completion<G: MazeGrid + ?Sized>(self, grid: &G, weights: &Weights) -> Self {
+        let mut state = self;
+        while let Some(next) = state.step(grid, weights) {
+            state = next;
+        }
+        state
+    }
+
+    // One step settles the cheapest cell in the frontier and updates the
+    // cost of reaching the cells linked to it
+    pub fn step<G: MazeGrid + ?Sized>(&self, grid: &G, weights: &Weights) -> Option<Self> {
+        let &(d, pos) = self.frontier.first()?;
+        let mut frontier = self.frontier.remove(&(d, pos));
+        let mut distances = self.distances.clone();
+        let mut max_distance = self.max_distance.max(d);
+
+        grid.links(pos).into_iter().for_each(|linked_pos| {
+            let distance = d + weights.get(linked_pos);
+            match distances.get(&linked_pos) {
+                Some(&existing) if existing <= distance => (),
+                existing => {
+                    if let Some(&existing) = existing {
+                        frontier = frontier.remove(&(existing, linked_pos));
+                    }
+                    distances = distances.insert(linked_pos, distance);
+                    frontier = frontier.insert((distance, linked_pos));
+                    max_distance = max_distance.max(distance);
+                }
+            }
+        });
+
+        Some(Self {
+            root: self.root,
+            distances,
+            frontier,
+            max_distance,
+        })
+    }
+
+    // The cheapest path from the root to the target, walking back through
+    // the cells whose cost accounts for the step into the next one. Empty if
+    // the target hasn't been reached.
+    pub fn path_to<G: MazeGrid + ?Sized>(&self, target: GridPos, grid: &G, weights: &Weights)
+            -> Vec<GridPos> {
+        let mut distance = match self.distances.get(&target) {
+            None => return Vec::new(),
+            Some(&distance) => distance,
+        };
+
+        let mut path = vec![target];
+        let mut current = target;
+        while current != self.root {
+            let previous = distance - weights.get(current);
+            current = grid.links(current).into_iter()
+                .find(|pos| self.distances.get(pos) == Some(&previous))
+                .unwrap();
+            distance = previous;
+            path.push(current);
+        }
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::grid_primitives::*;
+    use super::super::immutable_grid::*;
+    use super::super::dijkstra::*;
+    use super::{WeightedDijkstra, Weights};
+
+    fn pos(row: usize, col: usize) -> GridPos {
+        GridPos::new(Row(row), Col(col))
+    }
+
+    // a 3x2 loop: two routes from the bottom left to the bottom right
+    fn ring() -> ImmutableGrid {
+        ImmutableGrid::new(3, 2)
+            .link_cells(pos(0, 0), Direction::East)
+            .link_cells(pos(0, 1), Direction::East)
+            .link_cells(pos(0, 0), Direction::North)
+            .link_cells(pos(1, 0), Direction::East)
+            .link_cells(pos(1, 1), Direction::East)
+            .link_cells(pos(1, 2), Direction::South)
+    }
+
+    #[test]
+    fn unweighted_matches_dijkstra() {
+        let grid = ImmutableGrid::new(10, 8).run_recursive_backtracker_algorithm().braid(0.5);
+        let weighted = WeightedDijkstra::new(pos(0, 0)).run_to_completion(&grid, &Weights::new());
+        let unweighted = Dijkstra::new(pos(0, 0)).run_to_completion(&grid);
+        grid.positions().into_iter().for_each(|p| {
+            assert_eq!(weighted.distances.get(&p), unweighted.distances.get(&p));
+        });
+    }
+
+    #[test]
+    fn routes_around_expensive_cells() {
+        let grid = ring();
+        let weights = Weights::new().with_weight(pos(0, 1), 10);
+        let d = WeightedDijkstra::new(pos(0, 0)).run_to_completion(&grid, &weights);
+        assert_eq!(d.distances.get(&pos(0, 2)), Some(&4));
+        assert_eq!(d.path_to(pos(0, 2), &grid, &weights),
+                   vec![pos(0, 0), pos(1, 0), pos(1, 1), pos(1, 2), pos(0, 2)]);
+    }
+
+    #[test]
+    fn goes_through_expensive_cells_when_cheaper() {
+        let grid = ring();
+        let weights = Weights::new().with_weight(pos(0, 1), 2);
+        let d = WeightedDijkstra::new(pos(0, 0)).run_to_completion(&grid, &weights);
+        assert_eq!(d.distances.get(&pos(0, 2)), Some(&3));
+        assert_eq!(d.path_to(pos(0, 2), &grid, &weights), vec![pos(0, 0), pos(0, 1), pos(0, 2)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cells_cant_be_free() {
+        Weights::new().with_weight(pos(0, 1), 0);
+    }
+}